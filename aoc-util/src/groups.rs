@@ -0,0 +1,85 @@
+/// Groups the items of an iterator into arrays of exactly `N` items.
+/// This is a stable replacement for the nightly-only `Iterator::array_chunks`.
+/// If the iterator runs out partway through a group, that partial group isn't dropped,
+/// it's kept and can be read with `remainder`.
+pub struct Groups<I: Iterator, const N: usize> {
+    iter: I,
+    remainder: Vec<I::Item>,
+}
+
+impl<I: Iterator, const N: usize> Groups<I, N> {
+    /// Items left over after the last full group.
+    /// Always empty until the iterator has returned `None`.
+    pub fn remainder(&self) -> &[I::Item] {
+        &self.remainder
+    }
+}
+
+impl<I: Iterator, const N: usize> Iterator for Groups<I, N> {
+    type Item = [I::Item; N];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        assert!(N > 0, "groups must have at least one item");
+        if !self.remainder.is_empty() {
+            return None;
+        }
+        let group: Vec<_> = self.iter.by_ref().take(N).collect();
+        match group.try_into() {
+            Ok(group) => Some(group),
+            Err(partial) => {
+                self.remainder = partial;
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if !self.remainder.is_empty() {
+            return (0, Some(0));
+        }
+        let (lower, upper) = self.iter.size_hint();
+        (lower / N, upper.map(|upper| upper / N))
+    }
+}
+
+/// Adds the `groups` method to every iterator.
+pub trait GroupsExt: Iterator + Sized {
+    /// Group the items of this iterator into arrays of `N` items.
+    fn groups<const N: usize>(self) -> Groups<Self, N> {
+        Groups {
+            iter: self,
+            remainder: Vec::new(),
+        }
+    }
+}
+
+impl<I: Iterator> GroupsExt for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_groups() {
+        let groups: Vec<_> = (1..=6).groups::<3>().collect();
+        assert_eq!(groups, vec![[1, 2, 3], [4, 5, 6]]);
+    }
+
+    #[test]
+    fn test_remainder() {
+        let mut groups = (1..=8).groups::<3>();
+        assert_eq!(groups.next(), Some([1, 2, 3]));
+        assert_eq!(groups.next(), Some([4, 5, 6]));
+        assert_eq!(groups.remainder(), &[] as &[i32]);
+        assert_eq!(groups.next(), None);
+        assert_eq!(groups.next(), None);
+        assert_eq!(groups.remainder(), &[7, 8]);
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut groups = std::iter::empty::<char>().groups::<2>();
+        assert_eq!(groups.next(), None);
+        assert!(groups.remainder().is_empty());
+    }
+}
@@ -0,0 +1,6 @@
+//! Helpers shared between days. Add it to a day with
+//! `aoc-util = { path = "../aoc-util" }` under `[dependencies]`.
+
+pub mod groups;
+
+pub use groups::GroupsExt;
@@ -1,22 +1,27 @@
-#![feature(iter_array_chunks)]
-
 use std::collections::HashSet;
 
+use aoc_util::GroupsExt;
+
 fn main() {
     println!("q1: {}", q1(include_str!("../input.txt")));
     println!("q2: {}", q2(include_str!("../input.txt")));
 }
 
 fn q2(input: &str) -> u64 {
-    input
-        .lines()
-        .array_chunks()
+    let mut groups = input.lines().groups();
+    let total = groups
+        .by_ref()
         .map(|[a, b, c]| {
             let intersection = &(&set(a) & &set(b)) & &set(c);
             let duplicate = intersection.iter().next().unwrap().to_owned();
             priority(duplicate)
         })
-        .sum()
+        .sum();
+    assert!(
+        groups.remainder().is_empty(),
+        "every elf should be in a group of three"
+    );
+    total
 }
 
 fn q1(input: &str) -> u64 {
@@ -0,0 +1,80 @@
+use crate::{intervals::IntervalSet, Range};
+
+/// How all the elves on one line of the input overlap each other.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Group {
+    /// The sections every elf on the line was assigned, if there are any.
+    pub intersection: Option<Range>,
+    /// Positions of elves whose every section is also assigned to someone else on the line.
+    /// Each one could be sent home on their own without leaving any section uncovered
+    /// (but two elves with identical ranges can't both be sent home).
+    pub redundant: Vec<usize>,
+    /// Every section assigned to at least one elf on the line.
+    pub union: IntervalSet,
+}
+
+/// Analyse how the ranges on one line overlap.
+pub fn analyse(ranges: &[Range]) -> Group {
+    let intersection = ranges
+        .iter()
+        .copied()
+        .reduce(|(a, b), (c, d)| (a.max(c), b.min(d)))
+        .filter(|(start, end)| start <= end);
+    let redundant = (0..ranges.len())
+        .filter(|&i| {
+            let others: IntervalSet = ranges
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, &range)| range)
+                .collect();
+            others.gaps(ranges[i]).is_empty()
+        })
+        .collect();
+    Group {
+        intersection,
+        redundant,
+        union: ranges.iter().copied().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyse() {
+        let group = analyse(&[(2, 6), (4, 8), (5, 5), (3, 4)]);
+        assert_eq!(group.intersection, None);
+        // 5-5 is inside 2-6, and 3-4 is inside 2-6 too.
+        assert_eq!(group.redundant, vec![2, 3]);
+        assert_eq!(group.union.segments(), &[(2, 8)]);
+    }
+
+    #[test]
+    fn test_common_section() {
+        let group = analyse(&[(2, 6), (4, 8), (1, 4)]);
+        assert_eq!(group.intersection, Some((4, 4)));
+        // 1-4 and 4-8 cover 2-6 between them.
+        assert_eq!(group.redundant, vec![0]);
+        assert_eq!(group.union.coverage(), 8);
+    }
+
+    #[test]
+    fn test_covered_by_several() {
+        // 3-7 isn't inside any one range, but between them, 1-4 and 5-9 cover it.
+        let group = analyse(&[(1, 4), (3, 7), (5, 9)]);
+        assert_eq!(group.redundant, vec![1]);
+    }
+
+    #[test]
+    fn test_pair() {
+        let group = analyse(&[(2, 4), (6, 8)]);
+        assert_eq!(group.intersection, None);
+        assert!(group.redundant.is_empty());
+        assert_eq!(group.union.segments(), &[(2, 4), (6, 8)]);
+        let identical = analyse(&[(3, 5), (3, 5)]);
+        assert_eq!(identical.intersection, Some((3, 5)));
+        assert_eq!(identical.redundant, vec![0, 1]);
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    fmt,
+};
+
+use crate::Range;
+
+/// Identifies one elf: the line of the input their assignment was on (0-based),
+/// and their position within that line (0 for the leftmost range).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ElfId {
+    pub line: usize,
+    pub position: usize,
+}
+
+/// Shown 1-based, like the line numbers in parse errors.
+impl fmt::Display for ElfId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, range {}", self.line + 1, self.position + 1)
+    }
+}
+
+/// Every elf's assignment, stored in an interval tree so overlaps can be found without comparing
+/// every elf to every other elf.
+///
+/// The tree is implicit: assignments are sorted by start, and the node for any slice of them is
+/// its middle element. Each node also stores the greatest end of any assignment in its slice,
+/// so a query can skip slices which end before the query range begins.
+pub struct AssignmentIndex {
+    /// Sorted by range start.
+    entries: Vec<(Range, ElfId)>,
+    /// `max_end[i]` is the greatest end in the slice whose middle node is `i`.
+    max_end: Vec<u32>,
+    ranges: HashMap<ElfId, Range>,
+}
+
+impl FromIterator<(ElfId, Range)> for AssignmentIndex {
+    fn from_iter<T: IntoIterator<Item = (ElfId, Range)>>(iter: T) -> Self {
+        let mut entries: Vec<_> = iter.into_iter().map(|(elf, range)| (range, elf)).collect();
+        entries.sort_unstable();
+        let ranges = entries.iter().map(|&(range, elf)| (elf, range)).collect();
+        let mut index = Self {
+            max_end: vec![0; entries.len()],
+            entries,
+            ranges,
+        };
+        index.build(0, index.entries.len());
+        index
+    }
+}
+
+impl AssignmentIndex {
+    /// Fill in `max_end` for the slice `lo..hi`, returning the greatest end in it.
+    fn build(&mut self, lo: usize, hi: usize) -> Option<u32> {
+        if lo >= hi {
+            return None;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let (_, end) = self.entries[mid].0;
+        let max = [self.build(lo, mid), Some(end), self.build(mid + 1, hi)]
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap();
+        self.max_end[mid] = max;
+        Some(max)
+    }
+
+    /// How many elves are in the index?
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Every elf whose assignment shares at least one section with `range`.
+    pub fn overlapping(&self, range: Range) -> Vec<ElfId> {
+        let mut found = Vec::new();
+        self.search(0, self.entries.len(), range, &mut found);
+        found
+    }
+
+    /// Every other elf whose assignment shares at least one section with this elf's.
+    /// Returns None if the elf isn't in the index.
+    pub fn overlapping_elf(&self, elf: ElfId) -> Option<Vec<ElfId>> {
+        let range = *self.ranges.get(&elf)?;
+        let mut found = self.overlapping(range);
+        found.retain(|other| other != &elf);
+        Some(found)
+    }
+
+    fn search(&self, lo: usize, hi: usize, (start, end): Range, found: &mut Vec<ElfId>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        // Nothing in this slice reaches the query range.
+        if self.max_end[mid] < start {
+            return;
+        }
+        self.search(lo, mid, (start, end), found);
+        let ((mid_start, mid_end), elf) = self.entries[mid];
+        // Everything after `mid` starts at or after `mid_start`, so if it's past the query range,
+        // so are they.
+        if mid_start > end {
+            return;
+        }
+        if mid_end >= start {
+            found.push(elf);
+        }
+        self.search(mid + 1, hi, (start, end), found);
+    }
+
+    /// Every pair of elves whose assignments overlap, including pairs on the same line.
+    /// Sweeps across the assignments in order of start, so it only does work proportional to
+    /// the number of elves and the number of pairs found.
+    pub fn overlapping_pairs(&self) -> Vec<(ElfId, ElfId)> {
+        let mut pairs = Vec::new();
+        // Assignments which might still overlap the next one, ordered by soonest-ending.
+        let mut active: BinaryHeap<Reverse<(u32, ElfId)>> = BinaryHeap::new();
+        for &((start, end), elf) in &self.entries {
+            while let Some(Reverse((active_end, _))) = active.peek() {
+                if *active_end >= start {
+                    break;
+                }
+                active.pop();
+            }
+            for Reverse((_, other)) in &active {
+                pairs.push((*other.min(&elf), *other.max(&elf)));
+            }
+            active.push(Reverse((end, elf)));
+        }
+        pairs
+    }
+
+    /// How many pairs of elves have overlapping assignments, including pairs on the same line?
+    /// Doesn't need to list the pairs, so it's O(n log n) even if every elf overlaps every other.
+    pub fn count_overlapping_pairs(&self) -> usize {
+        // Two assignments are disjoint iff one of them ends before the other starts,
+        // so count those pairs and subtract them from the total.
+        let mut ends: Vec<u32> = self.entries.iter().map(|((_, end), _)| *end).collect();
+        ends.sort_unstable();
+        let disjoint: usize = self
+            .entries
+            .iter()
+            .map(|((start, _), _)| ends.partition_point(|end| end < start))
+            .sum();
+        let n = self.len();
+        n * n.saturating_sub(1) / 2 - disjoint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::OnReversed;
+
+    fn elf(line: usize, position: usize) -> ElfId {
+        ElfId { line, position }
+    }
+
+    fn example() -> AssignmentIndex {
+        crate::index_entries(
+            &crate::parse::parse_input(include_bytes!("../example.txt"), OnReversed::Reject)
+                .unwrap(),
+        )
+        .collect()
+    }
+
+    /// Check every pair, the slow way.
+    fn brute_force_pairs(index: &AssignmentIndex) -> Vec<(ElfId, ElfId)> {
+        let mut pairs = Vec::new();
+        for (i, &((a, b), x)) in index.entries.iter().enumerate() {
+            for &((c, d), y) in &index.entries[i + 1..] {
+                if a <= d && c <= b {
+                    pairs.push((x.min(y), x.max(y)));
+                }
+            }
+        }
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn test_display_is_one_based() {
+        assert_eq!(elf(388, 0).to_string(), "line 389, range 1");
+    }
+
+    #[test]
+    fn test_overlapping() {
+        let index = example();
+        let mut found = index.overlapping((9, 9));
+        found.sort();
+        assert_eq!(found, vec![elf(2, 1)]);
+        assert!(index.overlapping((10, 20)).is_empty());
+
+        // Elf 6-8 (line 0, right) overlaps everyone except 2-4, 2-3 and 4-5.
+        let found = index.overlapping_elf(elf(0, 1)).unwrap();
+        assert_eq!(found.len(), 8);
+        for not_overlapping in [elf(0, 0), elf(1, 0), elf(1, 1), elf(0, 1)] {
+            assert!(!found.contains(&not_overlapping));
+        }
+        assert!(index.overlapping_elf(elf(99, 0)).is_none());
+    }
+
+    #[test]
+    fn test_pairs_match_brute_force() {
+        let index = example();
+        let mut pairs = index.overlapping_pairs();
+        pairs.sort();
+        let expected = brute_force_pairs(&index);
+        assert_eq!(pairs, expected);
+        assert_eq!(index.count_overlapping_pairs(), expected.len());
+    }
+
+    #[test]
+    fn test_count_real_input() {
+        let lines =
+            crate::parse::parse_input(include_bytes!("../input.txt"), OnReversed::Reject).unwrap();
+        let index: AssignmentIndex = crate::index_entries(&lines).collect();
+        let pairs = index.overlapping_pairs();
+        assert_eq!(index.count_overlapping_pairs(), pairs.len());
+    }
+}
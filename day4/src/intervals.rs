@@ -0,0 +1,152 @@
+use crate::Range;
+
+/// A set of sections, stored as disjoint, sorted, non-adjacent ranges.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct IntervalSet {
+    segments: Vec<Range>,
+}
+
+impl IntervalSet {
+    /// The disjoint ranges making up this set, in ascending order.
+    /// Ranges which touch (e.g. 2-4 and 5-7) are merged into one (2-7).
+    pub fn segments(&self) -> &[Range] {
+        &self.segments
+    }
+
+    /// The smallest range containing every section in the set, or None if the set is empty.
+    pub fn bounds(&self) -> Option<Range> {
+        let (start, _) = self.segments.first()?;
+        let (_, end) = self.segments.last()?;
+        Some((*start, *end))
+    }
+
+    /// Add every section in the given range to the set.
+    pub fn insert(&mut self, (start, end): Range) {
+        // Find every existing segment that overlaps or touches the new range, and merge them.
+        let first = self
+            .segments
+            .partition_point(|&(_, seg_end)| seg_end.saturating_add(1) < start);
+        let last = self
+            .segments
+            .partition_point(|&(seg_start, _)| seg_start <= end.saturating_add(1));
+        let merged = match self.segments[first..last] {
+            [] => (start, end),
+            [(first_start, _), .., (_, last_end)] | [(first_start, last_end)] => {
+                (start.min(first_start), end.max(last_end))
+            }
+        };
+        self.segments.splice(first..last, [merged]);
+    }
+
+    /// How many sections are in the set?
+    pub fn coverage(&self) -> u64 {
+        self.segments.iter().map(|&range| len(range)).sum()
+    }
+
+    /// Every range of sections within `bounds` which isn't in the set.
+    pub fn gaps(&self, (lo, hi): Range) -> Vec<Range> {
+        let mut gaps = Vec::new();
+        // The first section which hasn't been checked yet. None once we've passed `hi`.
+        let mut next = Some(lo);
+        for &(start, end) in &self.segments {
+            let Some(n) = next else { break };
+            if start > hi {
+                break;
+            }
+            if start > n {
+                gaps.push((n, start - 1));
+            }
+            if end >= n {
+                next = end.checked_add(1);
+            }
+        }
+        if let Some(n) = next {
+            if n <= hi {
+                gaps.push((n, hi));
+            }
+        }
+        gaps
+    }
+}
+
+impl FromIterator<Range> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Range>>(iter: T) -> Self {
+        let mut set = Self::default();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+/// Number of sections in an inclusive range.
+fn len((start, end): Range) -> u64 {
+    u64::from(end) - u64::from(start) + 1
+}
+
+/// The most elves assigned to any one section at the same time.
+/// Sweeps across the sections, tracking how many ranges start and end at each point.
+pub fn max_depth(ranges: impl IntoIterator<Item = Range>) -> usize {
+    // Each range enters at its start and exits just after its end.
+    // Use u64 so the exit point of a range ending at u32::MAX doesn't overflow.
+    let mut events: Vec<(u64, isize)> = ranges
+        .into_iter()
+        .flat_map(|(start, end)| [(start.into(), 1), (u64::from(end) + 1, -1)])
+        .collect();
+    // At the same point, process exits before entries, so touching ranges don't count as overlapping.
+    events.sort_unstable();
+    let mut depth = 0;
+    let mut max = 0;
+    for (_, change) in events {
+        depth += change;
+        max = max.max(depth);
+    }
+    max as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union() {
+        let set: IntervalSet = [(5, 7), (2, 3), (10, 12), (4, 4), (11, 15)]
+            .into_iter()
+            .collect();
+        assert_eq!(set.segments(), &[(2, 7), (10, 15)]);
+        assert_eq!(set.coverage(), 12);
+        assert_eq!(set.bounds(), Some((2, 15)));
+        assert_eq!(IntervalSet::default().bounds(), None);
+    }
+
+    #[test]
+    fn test_insert_spanning_many() {
+        let mut set: IntervalSet = [(1, 1), (3, 3), (5, 5), (9, 9)].into_iter().collect();
+        set.insert((2, 6));
+        assert_eq!(set.segments(), &[(1, 6), (9, 9)]);
+    }
+
+    #[test]
+    fn test_gaps() {
+        let set: IntervalSet = [(2, 4), (8, 9)].into_iter().collect();
+        assert_eq!(set.gaps((1, 12)), vec![(1, 1), (5, 7), (10, 12)]);
+        assert_eq!(set.gaps((3, 8)), vec![(5, 7)]);
+        assert_eq!(set.gaps((2, 4)), vec![]);
+        assert_eq!(IntervalSet::default().gaps((0, 3)), vec![(0, 3)]);
+    }
+
+    #[test]
+    fn test_extreme_sections() {
+        let set: IntervalSet = [(0, 0), (u32::MAX - 1, u32::MAX)].into_iter().collect();
+        assert_eq!(set.gaps((0, u32::MAX)), vec![(1, u32::MAX - 2)]);
+        assert_eq!(set.coverage(), 3);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        assert_eq!(max_depth([]), 0);
+        assert_eq!(max_depth([(2, 4), (5, 7)]), 1);
+        assert_eq!(max_depth([(2, 4), (4, 7), (3, 4)]), 3);
+        assert_eq!(max_depth([(1, 10), (2, 3), (4, 5), (5, 9)]), 3);
+    }
+}
@@ -1,75 +1,218 @@
-use nom::{
-    character::complete::{char, line_ending, u32 as parse_u32},
-    combinator::map,
-    multi::separated_list1,
-    sequence::separated_pair,
-    IResult,
-};
+use index::{AssignmentIndex, ElfId};
+use intervals::IntervalSet;
+use parse::OnReversed;
+
+mod group;
+mod index;
+mod intervals;
+mod parse;
+mod schedule;
 
 fn main() {
     let input = include_bytes!("../input.txt");
-    let (q1, q2) = solve(input);
-    println!("q1: {q1}");
-    println!("q2: {q2}");
-}
+    let lines = match parse::parse_input(input, OnReversed::Reject) {
+        Ok(lines) => lines,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{error}");
+            }
+            std::process::exit(1);
+        }
+    };
+    let counts = solve(&lines);
+    println!("q1: {}", counts.q1());
+    println!("q2: {}", counts.q2());
+    println!("pairs which don't overlap: {}", counts.none);
+    println!("pairs which only touch at one end: {}", counts.touching);
+    println!("pairs which partly overlap: {}", counts.partial);
+    println!(
+        "pairs where the left range contains the right: {}",
+        counts.left_contains_right
+    );
+    println!(
+        "pairs where the right range contains the left: {}",
+        counts.right_contains_left
+    );
+    println!("pairs with identical ranges: {}", counts.identical);
 
-/// Parse the input, find how many ranges overlap
-/// (returning q1 and q2's definition of 'overlap' respectively)
-fn solve(input: &[u8]) -> (usize, usize) {
-    let ranges = parse_input(input);
-    let q1 = ranges
+    let shared: IntervalSet = lines
         .iter()
-        .filter(|q| matches!(q, Overlap::Complete))
-        .count();
-    let q2 = ranges
+        .flat_map(|line| pairs_within(line))
+        .filter_map(|pair| Overlap::from(pair).shared())
+        .collect();
+    println!("sections shared within a line: {:?}", shared.segments());
+    let groups: Vec<_> = lines.iter().map(|line| group::analyse(line)).collect();
+    println!(
+        "lines where every elf shares a section: {}",
+        groups.iter().filter(|g| g.intersection.is_some()).count()
+    );
+    println!(
+        "elves made redundant by others on their line: {}",
+        groups.iter().map(|g| g.redundant.len()).sum::<usize>()
+    );
+    let index: AssignmentIndex = index_entries(&lines).collect();
+    println!(
+        "pairs of elves on different lines which overlap: {}",
+        index.count_overlapping_pairs() - counts.q2()
+    );
+    let first_elf = ElfId {
+        line: 0,
+        position: 0,
+    };
+    if let Some(elves) = index.overlapping_elf(first_elf) {
+        println!("elves overlapping {first_elf}: {}", elves.len());
+    }
+    let cross_line_pairs = index
+        .overlapping_pairs()
+        .into_iter()
+        .filter(|(a, b)| a.line != b.line);
+    for (a, b) in cross_line_pairs.take(3) {
+        println!("e.g. {a} overlaps {b}");
+    }
+    print!("{}", schedule::render(&lines));
+    let dropped = schedule::min_drops(index_entries(&lines));
+    println!(
+        "dropping {} of {} elves would leave no overlaps at all",
+        dropped.len(),
+        index.len()
+    );
+    let assignments: Vec<Range> = lines.into_iter().flatten().collect();
+    let covered: IntervalSet = assignments.iter().copied().collect();
+    println!("sections assigned: {:?}", covered.segments());
+    println!("sections covered: {}", covered.coverage());
+    if let Some(bounds) = covered.bounds() {
+        println!("gaps: {:?}", covered.gaps(bounds));
+    }
+    println!(
+        "most elves on one section: {}",
+        intervals::max_depth(assignments)
+    );
+}
+
+/// Identify every elf in the parsed input, alongside their assignment.
+fn index_entries(lines: &[Vec<Range>]) -> impl Iterator<Item = (ElfId, Range)> + '_ {
+    lines.iter().enumerate().flat_map(|(line, ranges)| {
+        ranges
+            .iter()
+            .enumerate()
+            .map(move |(position, &range)| (ElfId { line, position }, range))
+    })
+}
+
+/// Every pair of ranges on the same line, with the leftmost range of each pair first.
+fn pairs_within(line: &[Range]) -> impl Iterator<Item = (Range, Range)> + '_ {
+    line.iter()
+        .enumerate()
+        .flat_map(move |(i, &left)| line[i + 1..].iter().map(move |&right| (left, right)))
+}
+
+/// Count how many pairs of ranges on the same line have each kind of overlap.
+fn solve(lines: &[Vec<Range>]) -> Counts {
+    lines
         .iter()
-        .filter(|q| matches!(q, Overlap::Partial | Overlap::Complete))
-        .count();
-    (q1, q2)
+        .flat_map(|line| pairs_within(line))
+        .map(Overlap::from)
+        .collect()
+}
+
+/// How many pairs of ranges on the same line had each kind of overlap.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Counts {
+    none: usize,
+    touching: usize,
+    partial: usize,
+    left_contains_right: usize,
+    right_contains_left: usize,
+    identical: usize,
+}
+
+impl Counts {
+    /// In how many pairs does one range fully contain the other?
+    fn q1(&self) -> usize {
+        self.left_contains_right + self.right_contains_left + self.identical
+    }
+
+    /// In how many pairs do the ranges overlap at all?
+    fn q2(&self) -> usize {
+        self.q1() + self.touching + self.partial
+    }
+}
+
+impl FromIterator<Overlap> for Counts {
+    fn from_iter<T: IntoIterator<Item = Overlap>>(iter: T) -> Self {
+        let mut counts = Self::default();
+        for overlap in iter {
+            let count = match overlap {
+                Overlap::None => &mut counts.none,
+                Overlap::Touching(_) => &mut counts.touching,
+                Overlap::Partial(_) => &mut counts.partial,
+                Overlap::LeftContainsRight(_) => &mut counts.left_contains_right,
+                Overlap::RightContainsLeft(_) => &mut counts.right_contains_left,
+                Overlap::Identical(_) => &mut counts.identical,
+            };
+            *count += 1;
+        }
+        counts
+    }
 }
 
 /// One elf's selection assignment -- an inclusive range of section IDs.
+/// The parser guarantees the start is never after the end.
 type Range = (u32, u32);
 
-/// Do the two ranges overlap each other at all?
+/// Do the two ranges overlap each other, and if so, how?
+/// Every variant except `None` carries the range of sections both elves share.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Overlap {
     /// e.g. 2-4, 6-8
     None,
-    /// e.g. 5-7, 7-9 overlaps in a single section, 7.
-    Partial,
-    /// e.g. 2-8, 3-7 overlaps all of the sections 3 through 7.
-    Complete,
+    /// e.g. 5-7, 7-9 only share section 7, where one range ends and the other begins.
+    Touching(Range),
+    /// e.g. 2-6, 4-8 share the sections 4 through 6.
+    Partial(Range),
+    /// e.g. 2-8, 3-7: the left range includes all of the right range.
+    LeftContainsRight(Range),
+    /// e.g. 6-6, 4-6: the right range includes all of the left range.
+    RightContainsLeft(Range),
+    /// e.g. 3-5, 3-5
+    Identical(Range),
+}
+
+impl Overlap {
+    /// The sections both ranges share, if any.
+    fn shared(&self) -> Option<Range> {
+        match *self {
+            Self::None => None,
+            Self::Touching(shared)
+            | Self::Partial(shared)
+            | Self::LeftContainsRight(shared)
+            | Self::RightContainsLeft(shared)
+            | Self::Identical(shared) => Some(shared),
+        }
+    }
 }
 
 /// You can calculate the kind of overlap that two ranges have.
 impl From<(Range, Range)> for Overlap {
     fn from(((a, b), (c, d)): (Range, Range)) -> Self {
-        if (a >= c && b <= d) || (c >= a && d <= b) {
-            return Self::Complete;
+        let shared = (a.max(c), b.min(d));
+        if shared.0 > shared.1 {
+            return Self::None;
         }
-        if (b >= c && b <= d) || (d >= a && d <= b) {
-            return Self::Partial;
+        if (a, b) == (c, d) {
+            return Self::Identical(shared);
         }
-        Self::None
-    }
-}
-
-/// For each line in the input, parse the two ranges, and calculate their overlap.
-fn parse_input(input: &[u8]) -> Vec<Overlap> {
-    fn parse_range(i: &[u8]) -> IResult<&[u8], Range> {
-        separated_pair(parse_u32, char('-'), parse_u32)(i)
-    }
-
-    fn parse_line(i: &[u8]) -> IResult<&[u8], Overlap> {
-        map(
-            separated_pair(parse_range, char(','), parse_range),
-            Overlap::from,
-        )(i)
+        if a <= c && b >= d {
+            return Self::LeftContainsRight(shared);
+        }
+        if c <= a && d >= b {
+            return Self::RightContainsLeft(shared);
+        }
+        if shared.0 == shared.1 {
+            return Self::Touching(shared);
+        }
+        Self::Partial(shared)
     }
-
-    let mut parser = separated_list1(line_ending, parse_line);
-    let (_remaining_input, range) = parser(input).expect("could not parse the problem input");
-    range
 }
 
 #[cfg(test)]
@@ -78,9 +221,52 @@ mod tests {
 
     #[test]
     fn test() {
-        let input = include_bytes!("../example.txt");
-        let (q1, q2) = solve(input);
-        assert_eq!(q1, 2);
-        assert_eq!(q2, 4);
+        let lines =
+            parse::parse_input(include_bytes!("../example.txt"), OnReversed::Reject).unwrap();
+        let counts = solve(&lines);
+        assert_eq!(counts.q1(), 2);
+        assert_eq!(counts.q2(), 4);
+        assert_eq!(
+            counts,
+            Counts {
+                none: 2,
+                touching: 1,
+                partial: 1,
+                left_contains_right: 1,
+                right_contains_left: 1,
+                identical: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_groups_of_three() {
+        let lines = parse::parse_input(b"2-4,3-5,4-6\n1-2,5-6,8-9", OnReversed::Reject).unwrap();
+        assert_eq!(lines[0], vec![(2, 4), (3, 5), (4, 6)]);
+        let counts = solve(&lines);
+        // 2-4,3-5 and 3-5,4-6 partially overlap, 2-4,4-6 touch. Nothing on line 2 overlaps.
+        assert_eq!(counts.partial, 2);
+        assert_eq!(counts.touching, 1);
+        assert_eq!(counts.none, 3);
+    }
+
+    #[test]
+    fn test_overlap() {
+        let cases = [
+            (((2, 4), (6, 8)), Overlap::None),
+            (((2, 3), (4, 5)), Overlap::None),
+            (((5, 7), (7, 9)), Overlap::Touching((7, 7))),
+            (((7, 9), (5, 7)), Overlap::Touching((7, 7))),
+            (((2, 6), (4, 8)), Overlap::Partial((4, 6))),
+            (((2, 8), (3, 7)), Overlap::LeftContainsRight((3, 7))),
+            (((2, 8), (2, 2)), Overlap::LeftContainsRight((2, 2))),
+            (((6, 6), (4, 6)), Overlap::RightContainsLeft((6, 6))),
+            (((3, 5), (3, 5)), Overlap::Identical((3, 5))),
+        ];
+        for (ranges, expected) in cases {
+            let actual = Overlap::from(ranges);
+            assert_eq!(actual, expected, "wrong overlap for {ranges:?}");
+            assert_eq!(actual.shared(), expected.shared());
+        }
     }
 }
@@ -0,0 +1,221 @@
+use std::fmt;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, u32 as parse_u32},
+    combinator::{all_consuming, map},
+    multi::separated_list1,
+    sequence::separated_pair,
+    IResult,
+};
+
+use crate::Range;
+
+/// What to do with a range whose end comes before its start, e.g. 8-2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnReversed {
+    /// Report it as an error.
+    Reject,
+    /// Swap the ends, so 8-2 means the same as 2-8.
+    Swap,
+}
+
+/// A problem with one line of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// 1-based, like a text editor.
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// The line isn't a comma-separated list of ranges.
+    Syntax,
+    /// The range at this (0-based) position on the line ends before it starts.
+    Reversed { position: usize, written: String },
+    /// The range at this (0-based) position on the line doesn't contain any sections, e.g. 3..3.
+    Empty { position: usize, written: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = self.line;
+        match &self.kind {
+            ErrorKind::Syntax => write!(f, "line {line}: expected ranges like 2-4,6-8"),
+            ErrorKind::Reversed { position, written } => write!(
+                f,
+                "line {line}, range {}: {written} ends before it starts",
+                position + 1
+            ),
+            ErrorKind::Empty { position, written } => write!(
+                f,
+                "line {line}, range {}: {written} contains no sections",
+                position + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A range as written in the input.
+#[derive(Debug, Clone, Copy)]
+enum Written {
+    /// e.g. 2-4 means sections 2, 3 and 4.
+    Inclusive(u32, u32),
+    /// e.g. 2..4 means sections 2 and 3.
+    Exclusive(u32, u32),
+}
+
+impl fmt::Display for Written {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Inclusive(start, end) => write!(f, "{start}-{end}"),
+            Self::Exclusive(start, end) => write!(f, "{start}..{end}"),
+        }
+    }
+}
+
+impl Written {
+    /// Convert to an inclusive range, or explain why it isn't valid.
+    fn validate(self, position: usize, on_reversed: OnReversed) -> Result<Range, ErrorKind> {
+        let (start, end) = match self {
+            Self::Inclusive(start, end) | Self::Exclusive(start, end) => (start, end),
+        };
+        let (start, end) = if start <= end {
+            (start, end)
+        } else if on_reversed == OnReversed::Swap {
+            (end, start)
+        } else {
+            return Err(ErrorKind::Reversed {
+                position,
+                written: self.to_string(),
+            });
+        };
+        match self {
+            Self::Inclusive(..) => Ok((start, end)),
+            Self::Exclusive(..) if start == end => Err(ErrorKind::Empty {
+                position,
+                written: self.to_string(),
+            }),
+            Self::Exclusive(..) => Ok((start, end - 1)),
+        }
+    }
+}
+
+/// For each line in the input, parse its comma-separated ranges.
+/// Ranges are written either `start-end` (inclusive) or `start..end` (excluding `end`).
+/// Returns every problem found, not just the first.
+pub fn parse_input(input: &[u8], on_reversed: OnReversed) -> Result<Vec<Vec<Range>>, Vec<Error>> {
+    fn parse_range(i: &[u8]) -> IResult<&[u8], Written> {
+        alt((
+            map(
+                separated_pair(parse_u32, tag(".."), parse_u32),
+                |(start, end)| Written::Exclusive(start, end),
+            ),
+            map(
+                separated_pair(parse_u32, char('-'), parse_u32),
+                |(start, end)| Written::Inclusive(start, end),
+            ),
+        ))(i)
+    }
+
+    fn parse_line(i: &[u8]) -> IResult<&[u8], Vec<Written>> {
+        all_consuming(separated_list1(char(','), parse_range))(i)
+    }
+
+    let input = input.strip_suffix(b"\n").unwrap_or(input);
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+    for (i, text) in input.split(|&byte| byte == b'\n').enumerate() {
+        let line = i + 1;
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        let Ok((_, written)) = parse_line(text) else {
+            errors.push(Error {
+                line,
+                kind: ErrorKind::Syntax,
+            });
+            continue;
+        };
+        let mut ranges = Vec::with_capacity(written.len());
+        for (position, range) in written.into_iter().enumerate() {
+            match range.validate(position, on_reversed) {
+                Ok(range) => ranges.push(range),
+                Err(kind) => errors.push(Error { line, kind }),
+            }
+        }
+        lines.push(ranges);
+    }
+    if errors.is_empty() {
+        Ok(lines)
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_example() {
+        let lines = parse_input(include_bytes!("../example.txt"), OnReversed::Reject).unwrap();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], vec![(2, 4), (6, 8)]);
+    }
+
+    #[test]
+    fn test_exclusive() {
+        let lines = parse_input(b"2..5,5-5\r\n1..2\n", OnReversed::Reject).unwrap();
+        assert_eq!(lines, vec![vec![(2, 4), (5, 5)], vec![(1, 1)]]);
+    }
+
+    #[test]
+    fn test_reversed() {
+        let input = b"2-4,6-8\n8-2,3-4\n1-1,5..3";
+        let errors = parse_input(input, OnReversed::Reject).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![
+                Error {
+                    line: 2,
+                    kind: ErrorKind::Reversed {
+                        position: 0,
+                        written: "8-2".to_owned()
+                    }
+                },
+                Error {
+                    line: 3,
+                    kind: ErrorKind::Reversed {
+                        position: 1,
+                        written: "5..3".to_owned()
+                    }
+                },
+            ]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "line 2, range 1: 8-2 ends before it starts"
+        );
+
+        let lines = parse_input(input, OnReversed::Swap).unwrap();
+        assert_eq!(lines[1], vec![(2, 8), (3, 4)]);
+        assert_eq!(lines[2], vec![(1, 1), (3, 4)]);
+    }
+
+    #[test]
+    fn test_bad_lines() {
+        let errors = parse_input(b"2-4,6-8\n3..3,1-2\n\n2-4;6-8", OnReversed::Swap).unwrap_err();
+        let summary: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            summary,
+            vec![
+                "line 2, range 1: 3..3 contains no sections",
+                "line 3: expected ranges like 2-4,6-8",
+                "line 4: expected ranges like 2-4,6-8",
+            ]
+        );
+    }
+}
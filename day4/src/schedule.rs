@@ -0,0 +1,185 @@
+use crate::{index::ElfId, Range};
+
+/// Shrink a pair of overlapping ranges so they no longer share any sections,
+/// giving up as few sections as possible. Ranges which don't overlap are returned unchanged.
+/// Returns None if it's impossible, i.e. both elves were assigned the same single section,
+/// in which case one of them has to be dropped instead.
+pub fn separate(left: Range, right: Range) -> Option<(Range, Range)> {
+    let left_first = split(left, right);
+    let right_first = split(right, left).map(|(new_right, new_left)| (new_left, new_right));
+    [left_first, right_first]
+        .into_iter()
+        .flatten()
+        .min_by_key(|&(new_left, new_right)| {
+            sections_lost(left, new_left) + sections_lost(right, new_right)
+        })
+}
+
+/// Shrink `first` and `second` so that every section of `first` comes before every section of
+/// `second`, giving up as few sections as possible.
+fn split((a, b): Range, (c, d): Range) -> Option<(Range, Range)> {
+    // `first` will end at some section x, and `second` will start after it,
+    // so x has to be somewhere in a..d.
+    if a >= d {
+        return None;
+    }
+    let shrink_to = |x: u32| ((a, b.min(x)), (c.max(x + 1), d));
+    // The number of sections lost only changes at these points, so the best split is one of them.
+    [a, c.saturating_sub(1), b, d - 1]
+        .into_iter()
+        .filter(|x| (a..d).contains(x))
+        .map(shrink_to)
+        .min_by_key(|&(first, second)| sections_lost((a, b), first) + sections_lost((c, d), second))
+}
+
+/// How many sections were given up when shrinking `before` to `after`?
+pub fn sections_lost((a, b): Range, (c, d): Range) -> u32 {
+    (b - a) - (d - c)
+}
+
+/// The fewest elves that could be sent home so that nobody else's assignments overlap.
+///
+/// Greedily keeps whichever remaining elf finishes soonest, since that leaves the most room for
+/// everyone else; any elf who overlaps someone already kept gets dropped.
+pub fn min_drops(assignments: impl IntoIterator<Item = (ElfId, Range)>) -> Vec<ElfId> {
+    let mut by_end: Vec<_> = assignments.into_iter().collect();
+    by_end.sort_unstable_by_key(|&(elf, (start, end))| (end, start, elf));
+    let mut dropped = Vec::new();
+    let mut last_kept_end = None;
+    for (elf, (start, end)) in by_end {
+        match last_kept_end {
+            Some(kept_end) if start <= kept_end => dropped.push(elf),
+            _ => last_kept_end = Some(end),
+        }
+    }
+    dropped
+}
+
+/// Write a cleanup schedule: every line whose ranges overlap, alongside the change that fixes it.
+/// Lines with two elves get their ranges shrunk; in bigger groups, some elves are dropped.
+pub fn render(lines: &[Vec<Range>]) -> String {
+    let show = |(start, end): Range| format!("{start}-{end}");
+    let show_line = |ranges: &[Range]| {
+        ranges
+            .iter()
+            .map(|&r| show(r))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    let mut out = String::new();
+    for (line, ranges) in lines.iter().enumerate() {
+        let after = match ranges[..] {
+            [left, right] => match separate(left, right) {
+                Some(fixed) if fixed == (left, right) => continue,
+                Some((new_left, new_right)) => format!(
+                    "{},{} ({} sections given up)",
+                    show(new_left),
+                    show(new_right),
+                    sections_lost(left, new_left) + sections_lost(right, new_right)
+                ),
+                None => "drop one elf".to_owned(),
+            },
+            _ => {
+                let elves = ranges
+                    .iter()
+                    .enumerate()
+                    .map(|(position, &range)| (ElfId { line, position }, range));
+                let dropped = min_drops(elves);
+                if dropped.is_empty() {
+                    continue;
+                }
+                let kept: Vec<_> = ranges
+                    .iter()
+                    .enumerate()
+                    .filter(|(position, _)| !dropped.iter().any(|elf| elf.position == *position))
+                    .map(|(_, &range)| range)
+                    .collect();
+                format!("{} ({} elves dropped)", show_line(&kept), dropped.len())
+            }
+        };
+        out.push_str(&format!(
+            "line {}: {} -> {after}\n",
+            line + 1,
+            show_line(ranges)
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::OnReversed;
+
+    #[test]
+    fn test_separate() {
+        let cases = [
+            // Already disjoint.
+            (((2, 4), (6, 8)), Some(((2, 4), (6, 8)))),
+            (((5, 7), (7, 9)), Some(((5, 6), (7, 9)))),
+            (((2, 6), (4, 8)), Some(((2, 3), (4, 8)))),
+            (((6, 8), (2, 6)), Some(((6, 8), (2, 5)))),
+            // The inner range survives, the outer one loses everything on one side of it.
+            (((2, 8), (3, 7)), Some(((2, 2), (3, 7)))),
+            (((6, 6), (4, 6)), Some(((6, 6), (4, 5)))),
+            (((3, 5), (3, 5)), Some(((3, 3), (4, 5)))),
+            (((4, 4), (4, 4)), None),
+        ];
+        for ((left, right), expected) in cases {
+            assert_eq!(separate(left, right), expected, "{left:?}, {right:?}");
+        }
+    }
+
+    #[test]
+    fn test_separate_loses_only_shared_sections() {
+        for line in
+            crate::parse::parse_input(include_bytes!("../input.txt"), OnReversed::Reject).unwrap()
+        {
+            let [left, right] = line[..] else {
+                unreachable!("the input has two elves per line")
+            };
+            let Some((new_left, new_right)) = separate(left, right) else {
+                continue;
+            };
+            assert!(new_left.1 < new_right.0 || new_right.1 < new_left.0);
+            let lost = sections_lost(left, new_left) + sections_lost(right, new_right);
+            let shared = match crate::Overlap::from((left, right)).shared() {
+                Some((start, end)) => end - start + 1,
+                None => 0,
+            };
+            // Partial overlaps only lose the shared sections.
+            // When one range contains the other, the outer range might have to lose more.
+            assert!(lost >= shared);
+            if matches!(
+                crate::Overlap::from((left, right)),
+                crate::Overlap::Partial(_) | crate::Overlap::Touching(_)
+            ) {
+                assert_eq!(lost, shared);
+            }
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let lines = [
+            vec![(2, 4), (6, 8)],
+            vec![(2, 6), (4, 8)],
+            vec![(1, 5), (2, 3), (4, 6)],
+        ];
+        assert_eq!(
+            render(&lines),
+            "line 2: 2-6,4-8 -> 2-3,4-8 (3 sections given up)\n\
+             line 3: 1-5,2-3,4-6 -> 2-3,4-6 (1 elves dropped)\n"
+        );
+    }
+
+    #[test]
+    fn test_min_drops() {
+        let lines = crate::parse::parse_input(include_bytes!("../example.txt"), OnReversed::Reject)
+            .unwrap();
+        let elves = crate::index_entries(&lines);
+        // At most four elves can stay, e.g. 2-3, 4-5, 6-6 and 7-9.
+        assert_eq!(min_drops(elves).len(), 8);
+        assert!(min_drops([]).is_empty());
+    }
+}
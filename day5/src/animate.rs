@@ -0,0 +1,198 @@
+use std::{fs, io, path::Path, thread, time::Duration};
+
+use super::{
+    crane::{self, Crane},
+    render::{self, Highlight},
+    validate::{self, StepError},
+    Rearrangement, Row,
+};
+
+/// One picture of the crates, partway through the procedure.
+pub struct Frame {
+    pub caption: String,
+    pub row: Row,
+    /// (column, height) of each crate which was just moved.
+    pub moved: Vec<(usize, usize)>,
+}
+
+impl Frame {
+    fn draw(&self, highlight: Highlight) -> String {
+        let mut out = format!("{}\n", self.caption);
+        render::draw(&mut out, &self.row, &self.moved, highlight)
+            .expect("writing to a String can't fail");
+        out
+    }
+}
+
+/// How often to take a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    /// After each rearrangement in the procedure.
+    Rearrangement,
+    /// After each time the crane lifts crates, e.g. after every single crate for the
+    /// CrateMover 9000.
+    Lift,
+}
+
+/// Carry out the procedure, taking a frame of the initial row and then after each step.
+pub fn frames(
+    crane: &dyn Crane,
+    mut row: Row,
+    rearrangements: &[Rearrangement],
+    granularity: Granularity,
+) -> Result<Vec<Frame>, StepError> {
+    let mut frames = vec![Frame {
+        caption: "initial arrangement".to_owned(),
+        row: row.clone(),
+        moved: Vec::new(),
+    }];
+    let total = rearrangements.len();
+    for (step, rearrangement) in rearrangements.iter().enumerate() {
+        validate::check_step(&row, step, rearrangement)?;
+        let caption = format!("step {}/{total}: {rearrangement}", step + 1);
+        let &Rearrangement { qty, src, dst } = rearrangement;
+        // The crates which moved are now the ones on top of the destination column.
+        let top_of_dst = |row: &Row, n: usize| {
+            let height = row.0[dst].len();
+            (height - n..height).map(|level| (dst, level)).collect()
+        };
+        match granularity {
+            Granularity::Lift if qty > 0 => {
+                let capacity = crane.capacity();
+                let mut remaining = qty;
+                let mut lift = 1;
+                while remaining > 0 {
+                    // Each of these is a single lift, moved the same way the crane would.
+                    let lifted = remaining.min(capacity);
+                    let single = Rearrangement {
+                        qty: lifted,
+                        src,
+                        dst,
+                    };
+                    crane::move_in_lifts(&mut row.0, capacity, &single);
+                    remaining -= lifted;
+                    frames.push(Frame {
+                        caption: format!("{caption}, lift {lift}"),
+                        moved: top_of_dst(&row, lifted),
+                        row: row.clone(),
+                    });
+                    lift += 1;
+                }
+            }
+            // Moving no crates takes no lifts, but still gets a frame so every step has one.
+            Granularity::Rearrangement | Granularity::Lift => {
+                crane.rearrange(&mut row, rearrangement);
+                frames.push(Frame {
+                    caption,
+                    moved: top_of_dst(&row, qty),
+                    row: row.clone(),
+                });
+            }
+        }
+    }
+    Ok(frames)
+}
+
+/// Show each frame in the terminal, replacing the previous one, waiting `delay` between them.
+pub fn play(frames: &[Frame], delay: Duration) {
+    const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+    for frame in frames {
+        print!("{CLEAR_SCREEN}{}", frame.draw(Highlight::Ansi));
+        thread::sleep(delay);
+    }
+}
+
+/// Write each frame to a numbered text file in `dir`, e.g. `frame-0000.txt`.
+pub fn write_files(frames: &[Frame], dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for (i, frame) in frames.iter().enumerate() {
+        fs::write(
+            dir.join(format!("frame-{i:04}.txt")),
+            frame.draw(Highlight::Braces),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crane::{CrateMover9000, CrateMover9001, LimitedCrane},
+        mv, parse,
+    };
+
+    #[test]
+    fn test_frames_per_rearrangement() {
+        let (row, rearrangements) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let frames = frames(
+            &CrateMover9001,
+            row,
+            &rearrangements,
+            Granularity::Rearrangement,
+        )
+        .unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[2].caption, "step 2/4: move 3 from 1 to 3");
+        assert_eq!(frames[2].moved, vec![(2, 1), (2, 2), (2, 3)]);
+        assert_eq!(
+            frames[2].draw(Highlight::Braces),
+            "step 2/4: move 3 from 1 to 3\n        {D}\n        {N}\n    [C] {Z}\n    [M] [P]\n 1   2   3\n"
+        );
+        assert_eq!(frames[4].row.top_of_each_column(), "MCD");
+    }
+
+    #[test]
+    fn test_frames_per_lift() {
+        let (row, rearrangements) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let frames = frames(&CrateMover9000, row, &rearrangements, Granularity::Lift).unwrap();
+        // One frame for the start, then one per crate moved.
+        assert_eq!(frames.len(), 1 + 1 + 3 + 2 + 1);
+        assert_eq!(frames[3].caption, "step 2/4: move 3 from 1 to 3, lift 2");
+        assert_eq!(frames[3].moved, vec![(2, 2)]);
+        assert_eq!(frames.last().unwrap().row.top_of_each_column(), "CMZ");
+    }
+
+    #[test]
+    fn test_lifts_follow_the_crane() {
+        let (row, _) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let frames = frames(
+            &LimitedCrane(2),
+            row,
+            &[mv(3, 1, 2), mv(0, 0, 1)],
+            Granularity::Lift,
+        )
+        .unwrap();
+        // Two lifts for the first step, and a frame for the second even though nothing moves.
+        let captions: Vec<_> = frames[1..].iter().map(|f| f.caption.as_str()).collect();
+        assert_eq!(
+            captions,
+            vec![
+                "step 1/2: move 3 from 2 to 3, lift 1",
+                "step 1/2: move 3 from 2 to 3, lift 2",
+                "step 2/2: move 0 from 1 to 2",
+            ]
+        );
+        assert_eq!(frames[1].moved, vec![(2, 1), (2, 2)]);
+        assert!(frames[3].moved.is_empty());
+        // The first lift takes the top two crates together, keeping their order.
+        assert_eq!(frames[3].row.0[2], ["P", "C", "D", "M"]);
+    }
+
+    #[test]
+    fn test_write_files() {
+        let (row, rearrangements) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let frames = frames(
+            &CrateMover9001,
+            row,
+            &rearrangements,
+            Granularity::Rearrangement,
+        )
+        .unwrap();
+        let dir = std::env::temp_dir().join(format!("day5-frames-{}", std::process::id()));
+        write_files(&frames, &dir).unwrap();
+        let last = fs::read_to_string(dir.join("frame-0004.txt")).unwrap();
+        assert!(last.starts_with("step 4/4: move 1 from 1 to 2\n"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use super::{
+    crane::{Crane, CrateMover9000, CrateMover9001},
+    Rearrangement, Row,
+};
+
+/// A small, deterministic pseudo-random number generator (xorshift),
+/// so generated inputs are the same every run.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// A number in 0..n.
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+/// Generate a row of `columns` columns holding `crates` crates between them,
+/// and a valid procedure of `moves` rearrangements, each moving up to `MAX_QTY` crates.
+pub fn generate(columns: usize, crates: usize, moves: usize) -> (Row, Vec<Rearrangement>) {
+    let mut rng = Rng(0x2022_1205);
+    let mut row = Row(vec![Vec::new(); columns]);
+    for i in 0..crates {
+        let label = char::from(b'A' + (i % 26) as u8).to_string();
+        row.0[rng.below(columns)].push(label);
+    }
+    let mut heights: Vec<usize> = row.0.iter().map(Vec::len).collect();
+    let mut procedure = Vec::with_capacity(moves);
+    while procedure.len() < moves {
+        let src = rng.below(columns);
+        let dst = rng.below(columns);
+        if src == dst || heights[src] == 0 {
+            continue;
+        }
+        const MAX_QTY: usize = 1000;
+        let qty = 1 + rng.below(heights[src].min(MAX_QTY));
+        heights[src] -= qty;
+        heights[dst] += qty;
+        procedure.push(Rearrangement { qty, src, dst });
+    }
+    (row, procedure)
+}
+
+/// How the crates used to be moved: popped one at a time into a buffer, which was then
+/// reversed if the crane keeps their order.
+fn rearrange_one_at_a_time(row: &mut Row, rearrangement: &Rearrangement, keep_order: bool) {
+    let mut buf = Vec::new();
+    for _ in 0..rearrangement.qty {
+        let to_move = row.0[rearrangement.src]
+            .pop()
+            .expect("not enough crates in column");
+        buf.push(to_move);
+    }
+    if keep_order {
+        buf.reverse();
+    }
+    row.0[rearrangement.dst].extend(buf);
+}
+
+fn time(f: impl FnOnce() -> Row) -> (Duration, Row) {
+    let start = Instant::now();
+    let row = f();
+    (start.elapsed(), row)
+}
+
+/// Time the old and new ways of moving crates on a generated procedure, and print the results.
+pub fn run(columns: usize, crates: usize, moves: usize) {
+    let (initial, procedure) = generate(columns, crates, moves);
+    println!("{moves} moves of {crates} crates between {columns} columns");
+    for (crane, keep_order) in [
+        (&CrateMover9000 as &dyn Crane, false),
+        (&CrateMover9001, true),
+    ] {
+        let (old, old_row) = time(|| {
+            let mut row = initial.clone();
+            for rearrangement in &procedure {
+                rearrange_one_at_a_time(&mut row, rearrangement, keep_order);
+            }
+            row
+        });
+        let (new, new_row) = time(|| {
+            let mut row = initial.clone();
+            for rearrangement in &procedure {
+                crane.rearrange(&mut row, rearrangement);
+            }
+            row
+        });
+        assert_eq!(old_row, new_row, "both ways should give the same answer");
+        println!(
+            "{}: one at a time {old:?}, bulk {new:?} ({:.1}x faster)",
+            crane.name(),
+            old.as_secs_f64() / new.as_secs_f64()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validate;
+
+    #[test]
+    fn test_generated_procedure_is_valid() {
+        let (row, procedure) = generate(9, 1000, 1000);
+        assert_eq!(row.0.iter().map(Vec::len).sum::<usize>(), 1000);
+        assert!(validate::dry_run(&row, &procedure).is_empty());
+    }
+
+    #[test]
+    fn test_bulk_matches_one_at_a_time() {
+        let (initial, procedure) = generate(5, 500, 200);
+        for (crane, keep_order) in [
+            (&CrateMover9000 as &dyn Crane, false),
+            (&CrateMover9001, true),
+        ] {
+            let mut old = initial.clone();
+            let mut new = initial.clone();
+            for rearrangement in &procedure {
+                rearrange_one_at_a_time(&mut old, rearrangement, keep_order);
+                crane.rearrange(&mut new, rearrangement);
+            }
+            assert_eq!(old, new);
+        }
+    }
+}
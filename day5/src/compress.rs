@@ -0,0 +1,172 @@
+use super::{
+    crane::Crane,
+    validate::{self, StepError},
+    Rearrangement, Row,
+};
+
+/// A shorter procedure with the same result as the original.
+#[derive(Debug)]
+pub struct Compressed {
+    pub procedure: Vec<Rearrangement>,
+    /// How many fewer rearrangements it has than the original.
+    pub saved: usize,
+}
+
+/// Rewrite the procedure into a shorter one which leaves the crates in the same places, when
+/// carried out by this crane. Removes rearrangements which do nothing, merges consecutive ones
+/// between the same columns, and cancels out ones which are immediately undone.
+///
+/// Returns an error if the original procedure can't be carried out. In debug builds, also checks
+/// the rewrite by carrying out both procedures, and panics if they don't match, since that means
+/// one of the rewrite rules is wrong.
+pub fn compress(
+    crane: &dyn Crane,
+    initial: &Row,
+    procedure: &[Rearrangement],
+) -> Result<Compressed, StepError> {
+    let mut expected = initial.clone();
+    validate::run(crane, &mut expected, procedure)?;
+    let capacity = crane.capacity();
+    let mut compressed: Vec<Rearrangement> = Vec::with_capacity(procedure.len());
+    for rearrangement in procedure {
+        push(&mut compressed, rearrangement.clone(), capacity);
+    }
+
+    if cfg!(debug_assertions) {
+        let mut actual = initial.clone();
+        let result = validate::run(crane, &mut actual, &compressed);
+        assert_eq!(result, Ok(()), "compressed procedure should be valid");
+        assert_eq!(
+            actual, expected,
+            "compressed procedure should have the same result"
+        );
+    }
+    Ok(Compressed {
+        saved: procedure.len() - compressed.len(),
+        procedure: compressed,
+    })
+}
+
+/// Add a rearrangement to the end of a procedure, combining it with the previous one if possible.
+fn push(procedure: &mut Vec<Rearrangement>, next: Rearrangement, capacity: usize) {
+    if next.qty == 0 || next.src == next.dst {
+        return;
+    }
+    let Some(prev) = procedure.last_mut() else {
+        procedure.push(next);
+        return;
+    };
+    // Moving crates one at a time means two moves between the same columns are the same as one.
+    // Otherwise, that only works if the first move's lifts were all full, so the second move's
+    // lifts pick up where it left off.
+    if (prev.src, prev.dst) == (next.src, next.dst) && prev.qty % capacity == 0 {
+        prev.qty += next.qty;
+        return;
+    }
+    if (prev.src, prev.dst) == (next.dst, next.src) {
+        if capacity == 1 {
+            // Moving crates back one at a time undoes the last moves first, so the two moves
+            // cancel out as far as they overlap.
+            let prev = procedure.pop().unwrap();
+            let net = if prev.qty >= next.qty {
+                Rearrangement {
+                    qty: prev.qty - next.qty,
+                    ..prev
+                }
+            } else {
+                Rearrangement {
+                    qty: next.qty - prev.qty,
+                    ..next
+                }
+            };
+            // What's left might now combine with the rearrangement before.
+            return push(procedure, net, capacity);
+        }
+        // A single lift is undone by lifting the same crates straight back.
+        if prev.qty == next.qty && prev.qty <= capacity {
+            procedure.pop();
+            return;
+        }
+    }
+    procedure.push(next);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bench,
+        crane::{CrateMover9000, CrateMover9001, LimitedCrane},
+        mv, parse,
+    };
+
+    #[test]
+    fn test_example() {
+        let (row, procedure) = parse::entire_input(include_bytes!("../example")).unwrap();
+        // Moving 2 crates from column 2 to 1 and then one back is the same as just moving one,
+        // when they're moved one at a time.
+        let compressed = compress(&CrateMover9000, &row, &procedure).unwrap();
+        assert_eq!(compressed.saved, 1);
+        assert_eq!(compressed.procedure.last(), Some(&mv(1, 1, 0)));
+        assert_eq!(
+            compress(&CrateMover9001, &row, &procedure).unwrap().saved,
+            0
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let (row, _) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let procedure = [
+            mv(0, 0, 1),
+            mv(1, 1, 1),
+            mv(1, 1, 0),
+            mv(2, 1, 0),
+            mv(3, 0, 2),
+            mv(3, 2, 0),
+        ];
+        let compressed = compress(&CrateMover9000, &row, &procedure).unwrap();
+        assert_eq!(compressed.procedure, vec![mv(3, 1, 0)]);
+        assert_eq!(compressed.saved, 5);
+
+        // The CrateMover 9001 can't merge moves, but can still cancel them out.
+        let compressed = compress(&CrateMover9001, &row, &procedure).unwrap();
+        assert_eq!(compressed.procedure, vec![mv(1, 1, 0), mv(2, 1, 0)]);
+
+        // This crane needs two lifts to move 3 crates, so moving them back isn't the same.
+        let compressed = compress(&LimitedCrane(2), &row, &procedure).unwrap();
+        assert_eq!(compressed.procedure, procedure[2..]);
+        // But moving 2 crates then 1 is the same as moving 3 at once.
+        let compressed = compress(&LimitedCrane(2), &row, &[mv(2, 1, 0), mv(1, 1, 0)]).unwrap();
+        assert_eq!(compressed.procedure, vec![mv(3, 1, 0)]);
+    }
+
+    #[test]
+    fn test_partial_cancel() {
+        let (row, _) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let procedure = [mv(3, 1, 2), mv(1, 2, 1), mv(2, 2, 1), mv(1, 2, 1)];
+        let compressed = compress(&CrateMover9000, &row, &procedure).unwrap();
+        assert_eq!(compressed.procedure, vec![mv(1, 2, 1)]);
+    }
+
+    #[test]
+    fn test_generated_procedures() {
+        // Generated procedures have lots of moves between the same few columns.
+        let (row, procedure) = bench::generate(3, 200, 2000);
+        for crane in [
+            &CrateMover9000 as &dyn Crane,
+            &CrateMover9001,
+            &LimitedCrane(7),
+        ] {
+            // `compress` checks the result itself in debug builds, which tests are.
+            compress(crane, &row, &procedure).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_invalid_procedure() {
+        let (row, _) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let error = compress(&CrateMover9000, &row, &[mv(1, 1, 0), mv(5, 0, 1)]).unwrap_err();
+        assert_eq!(error.step, 1);
+    }
+}
@@ -0,0 +1,115 @@
+use super::{lift, lift_reversed, Rearrangement, Row};
+
+/// A model of crane, which decides how crates get moved between columns.
+pub trait Crane {
+    /// Name to show in the output.
+    fn name(&self) -> String;
+
+    /// The most crates this crane can pick up in one lift.
+    /// Crates lifted together keep their order when they're put down.
+    fn capacity(&self) -> usize;
+
+    /// Use the crane to rearrange crates, lifting as many as it can at a time.
+    fn rearrange(&self, row: &mut Row, rearrangement: &Rearrangement) {
+        move_in_lifts(&mut row.0, self.capacity(), rearrangement);
+    }
+}
+
+/// Carry out a rearrangement with a crane that lifts up to `capacity` crates at a time,
+/// by splitting it into as many full lifts as it takes and then one for whatever's left.
+pub fn move_in_lifts<T>(columns: &mut [Vec<T>], capacity: usize, rearrangement: &Rearrangement) {
+    let &Rearrangement { qty, src, dst } = rearrangement;
+    if capacity == 1 {
+        // Equivalent to lifting one crate at a time, but moves them all in one go.
+        lift_reversed(columns, src, dst, qty);
+        return;
+    }
+    let mut remaining = qty;
+    while remaining > 0 {
+        let lifted = remaining.min(capacity);
+        lift(columns, src, dst, lifted);
+        remaining -= lifted;
+    }
+}
+
+/// Moves one crate at a time, so a group of moved crates ends up in reverse order.
+pub struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "CrateMover 9000".to_owned()
+    }
+
+    fn capacity(&self) -> usize {
+        1
+    }
+}
+
+/// Moves every crate in a rearrangement at once, so they keep their order.
+pub struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "CrateMover 9001".to_owned()
+    }
+
+    fn capacity(&self) -> usize {
+        usize::MAX
+    }
+}
+
+/// Like the CrateMover 9001, but can only lift so many crates at once.
+/// Bigger rearrangements get split into several lifts.
+pub struct LimitedCrane(pub usize);
+
+impl Crane for LimitedCrane {
+    fn name(&self) -> String {
+        format!("crane lifting up to {} crates", self.0)
+    }
+
+    fn capacity(&self) -> usize {
+        assert!(self.0 > 0, "a crane must be able to lift something");
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row() -> Row {
+        Row(vec![
+            ["A", "B", "C", "D", "E"].map(String::from).to_vec(),
+            vec![],
+        ])
+    }
+
+    const MOVE_ALL: Rearrangement = Rearrangement {
+        qty: 5,
+        src: 0,
+        dst: 1,
+    };
+
+    #[test]
+    fn test_limited_capacity() {
+        let mut r = row();
+        LimitedCrane(2).rearrange(&mut r, &MOVE_ALL);
+        // Lifts DE, then BC, then A.
+        assert_eq!(r.0[1], ["D", "E", "B", "C", "A"]);
+    }
+
+    #[test]
+    fn test_limited_matches_other_cranes() {
+        let mut limited = row();
+        let mut original = row();
+        LimitedCrane(1).rearrange(&mut limited, &MOVE_ALL);
+        CrateMover9000.rearrange(&mut original, &MOVE_ALL);
+        assert_eq!(limited.0, original.0);
+
+        let mut limited = row();
+        let mut original = row();
+        LimitedCrane(5).rearrange(&mut limited, &MOVE_ALL);
+        CrateMover9001.rearrange(&mut original, &MOVE_ALL);
+        assert_eq!(limited.0, original.0);
+    }
+}
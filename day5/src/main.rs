@@ -1,89 +1,356 @@
+use std::{path::PathBuf, time::Duration};
+
+use animate::Granularity;
+use crane::{Crane, CrateMover9000, CrateMover9001, LimitedCrane};
+
+mod animate;
+mod bench;
+mod compress;
+mod crane;
 mod parse;
+mod provenance;
+mod render;
+mod search;
+mod undo;
+mod validate;
 
+/// Usage:
+///   day5
+///     Solve Q1 and Q2, and compare other cranes.
+///   day5 animate [9000|9001|<capacity>] [--lifts] [--delay-ms <ms>] [--out <dir>]
+///     Watch a crane carry out the procedure, a frame per rearrangement (or per lift),
+///     either in the terminal or written to numbered files in a directory.
+///   day5 search [--from <file>] (<tops> | --drawing <file>) [9000|9001|<capacity>]
+///     Find the shortest procedure which leaves the given crates on top of each column,
+///     or which produces the drawing in the given file. Starts from the drawing in the
+///     `--from` file, or else the example's drawing (the real input is too big to search).
+///   day5 rewind <undo> [<redo>] [9000|9001|<capacity>]
+///     Carry out the procedure, then undo the last few rearrangements and redo some of them.
+///   day5 bench [<moves>] [<crates>] [<columns>]
+///     Time moving crates one at a time against moving them in bulk, on a generated procedure.
+///     Run it with `cargo run --release`.
+///   day5 provenance [9000|9001|<capacity>]
+///     Show where the crates on top of each column came from, and which crates moved most.
+///   day5 compress [9000|9001|<capacity>]
+///     Print a shorter procedure with the same result as the input's, for the given crane.
+///   day5 inverse
+///     Print the procedure which takes the final drawing back to the initial one
+///     (the same for both the CrateMover 9000 and 9001).
 fn main() {
-    let (a1, a2) = solve(include_bytes!("../input"));
-    println!("Q1: {a1}");
-    println!("Q2: {a2}");
+    let cranes: [&dyn Crane; 4] = [
+        &CrateMover9000,
+        &CrateMover9001,
+        &LimitedCrane(2),
+        &LimitedCrane(3),
+    ];
+    let input = include_bytes!("../input");
+    let (row, rearrangements) = parse::entire_input(input).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+    let problems = validate::dry_run(&row, &rearrangements);
+    if !problems.is_empty() {
+        for problem in problems {
+            eprintln!("{problem}");
+        }
+        std::process::exit(1);
+    }
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("animate") => return animate(&args[1..], row, &rearrangements),
+        Some("search") => return search(&args[1..]),
+        Some("bench") => {
+            let mut numbers = args[1..].iter().map(|n| n.parse().expect("not a number"));
+            let moves = numbers.next().unwrap_or(1_000_000);
+            let crates = numbers.next().unwrap_or(1_000_000);
+            let columns = numbers.next().unwrap_or(9);
+            return bench::run(columns, crates, moves);
+        }
+        Some("provenance") => {
+            let crane = parse_crane(args.get(1).map_or("9001", String::as_str));
+            let provenance = provenance::Provenance::run(crane.as_ref(), &row, &rearrangements);
+            return print_provenance(&provenance);
+        }
+        Some("compress") => {
+            let crane = parse_crane(args.get(1).map_or("9001", String::as_str));
+            let compressed = compress::compress(crane.as_ref(), &row, &rearrangements)
+                .expect("procedure was already checked");
+            for rearrangement in compressed.procedure {
+                println!("{rearrangement}");
+            }
+            eprintln!("saved {} steps", compressed.saved);
+            return;
+        }
+        Some("rewind") => return rewind(&args[1..], row, &rearrangements),
+        Some("inverse") => {
+            for rearrangement in undo::inverse_procedure(&rearrangements) {
+                println!("{rearrangement}");
+            }
+            return;
+        }
+        _ => {}
+    }
+    let final_rows = solve(&row, &rearrangements, &cranes).expect("procedure was already checked");
+    for (crane, final_row) in cranes.iter().zip(final_rows) {
+        println!("{}: {}", crane.name(), final_row.top_of_each_column());
+        println!("{final_row}");
+        let compressed = compress::compress(*crane, &row, &rearrangements)
+            .expect("procedure was already checked");
+        println!(
+            "the procedure could be {} steps shorter\n",
+            compressed.saved
+        );
+    }
 }
 
-/// Returns answer to q1 and q2, given the problem input.
-fn solve(input: &[u8]) -> (String, String) {
-    let (row, rearrangements) = parse::entire_input(input);
-
-    // Solve q1
-    let mut row_q1 = row.clone();
-    for rearrangement in &rearrangements {
-        row_q1.move_crates(rearrangement, false);
+/// Animate the procedure, configured by the command-line arguments after `animate`.
+fn animate(args: &[String], row: Row, rearrangements: &[Rearrangement]) {
+    let mut crane: Box<dyn Crane> = Box::new(CrateMover9001);
+    let mut granularity = Granularity::Rearrangement;
+    let mut delay = Duration::from_millis(200);
+    let mut out_dir = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lifts" => granularity = Granularity::Lift,
+            "--delay-ms" => {
+                let ms = args.next().and_then(|ms| ms.parse().ok());
+                delay = Duration::from_millis(ms.expect("--delay-ms needs a number"));
+            }
+            "--out" => out_dir = Some(PathBuf::from(args.next().expect("--out needs a directory"))),
+            other => crane = parse_crane(other),
+        }
+    }
+    let frames = animate::frames(crane.as_ref(), row, rearrangements, granularity)
+        .expect("procedure was already checked");
+    match out_dir {
+        Some(dir) => {
+            animate::write_files(&frames, &dir).expect("could not write frames");
+            println!("wrote {} frames to {}", frames.len(), dir.display());
+        }
+        None => animate::play(&frames, delay),
     }
-    let answer1 = row_q1.top_of_each_column();
+}
 
-    // Solve q2
-    let mut row_q2 = row;
-    for rearrangement in &rearrangements {
-        row_q2.move_crates(rearrangement, true);
+/// Search for the shortest procedure, configured by the command-line arguments after `search`.
+fn search(args: &[String]) {
+    let (row, args) = match args {
+        [flag, path, rest @ ..] if flag == "--from" => (read_drawing(path), rest),
+        _ => {
+            let (row, _) = parse::entire_input(include_bytes!("../example"))
+                .expect("the example is well-formed");
+            (row, args)
+        }
+    };
+    let (goal, crane_arg) = match args {
+        [flag, path, rest @ ..] if flag == "--drawing" => {
+            (search::Goal::Row(read_drawing(path)), rest.first())
+        }
+        [tops, rest @ ..] => (search::Goal::Tops(tops.to_owned()), rest.first()),
+        [] => panic!("search needs the crates to leave on top, or --drawing <file>"),
+    };
+    let crane = parse_crane(crane_arg.map_or("9001", String::as_str));
+    const MAX_STATES: usize = 1_000_000;
+    match search::shortest_procedure(crane.as_ref(), &row, &goal, MAX_STATES) {
+        Some(procedure) => {
+            for rearrangement in procedure {
+                println!("{rearrangement}");
+            }
+        }
+        None => eprintln!("no procedure found with the {}", crane.name()),
     }
-    let answer2 = row_q2.top_of_each_column();
+}
 
-    (answer1, answer2)
+/// Read a file holding just a drawing of stacked crates, with its footer.
+fn read_drawing(path: &str) -> Row {
+    let drawing = std::fs::read(path).expect("could not read the drawing");
+    let (_, row) = parse::drawing(&drawing).expect("could not parse the drawing");
+    row
 }
 
-/// Each element of the vec is a column of crates. Columns may be empty.
-#[derive(Debug, Clone)]
-pub struct Row(Vec<Vec<char>>);
+/// Undo and redo some of the procedure, configured by the command-line arguments after `rewind`.
+fn rewind(args: &[String], mut row: Row, rearrangements: &[Rearrangement]) {
+    let counts: Vec<usize> = args
+        .iter()
+        .map_while(|arg| arg.parse().ok())
+        .take(2)
+        .collect();
+    let (undo_count, redo_count) = match counts[..] {
+        [undo_count] => (undo_count, 0),
+        [undo_count, redo_count] => (undo_count, redo_count),
+        _ => panic!("rewind needs the number of rearrangements to undo"),
+    };
+    let crane = parse_crane(args.get(counts.len()).map_or("9001", String::as_str));
+    let mut log = undo::UndoLog::default();
+    for rearrangement in rearrangements {
+        row.apply_logged(crane.as_ref(), rearrangement, &mut log)
+            .expect("procedure was already checked");
+    }
+    for _ in 0..undo_count {
+        match row.undo(&mut log) {
+            Some(rearrangement) => println!("undo {rearrangement}"),
+            None => break,
+        }
+    }
+    for _ in 0..redo_count {
+        match row.redo(&mut log) {
+            Some(rearrangement) => println!("redo {rearrangement}"),
+            None => break,
+        }
+    }
+    println!("{row}");
+}
 
-impl Row {
-    /// Put `other` on top of `self`.
-    fn stack(mut self, Self(other): Self) -> Self {
-        for (i, column) in other.iter().enumerate() {
-            self.0[i].extend(column);
+/// Print the history of the crates on top, and some other interesting crates.
+fn print_provenance(provenance: &provenance::Provenance) {
+    let describe = |c: &provenance::Crate| {
+        let (column, height) = c.origin;
+        format!(
+            "[{}] started at column {} height {}, moved {} times",
+            c.label,
+            column + 1,
+            height + 1,
+            c.moves.len()
+        )
+    };
+    let columns = provenance.row().0.len();
+    for column in 0..columns {
+        let Some(top) = provenance.top(column) else {
+            continue;
+        };
+        println!("top of column {}: {}", column + 1, describe(top));
+        for m in &top.moves {
+            println!("  step {}: {} -> {}", m.step + 1, m.from + 1, m.to + 1);
         }
-        self
     }
+    println!("crates never moved: {}", provenance.never_moved().count());
+    if let Some(most_moved) = provenance.most_moved() {
+        println!("most moved: {}", describe(most_moved));
+    }
+}
+
+/// Pick a crane from a command-line argument: 9000, 9001, or a lift capacity.
+fn parse_crane(arg: &str) -> Box<dyn Crane> {
+    match arg {
+        "9000" => Box::new(CrateMover9000),
+        "9001" => Box::new(CrateMover9001),
+        capacity => match capacity.parse() {
+            Ok(capacity) if capacity > 0 => Box::new(LimitedCrane(capacity)),
+            _ => panic!("unknown argument {capacity}"),
+        },
+    }
+}
+
+/// Run the procedure with each crane, returning the final row of crates.
+/// Q1 and Q2 are the crates on top of each column, using the CrateMover 9000 and 9001 respectively.
+fn solve(
+    row: &Row,
+    rearrangements: &[Rearrangement],
+    cranes: &[&dyn Crane],
+) -> Result<Vec<Row>, validate::StepError> {
+    cranes
+        .iter()
+        .map(|crane| {
+            let mut row = row.clone();
+            validate::run(*crane, &mut row, rearrangements)?;
+            Ok(row)
+        })
+        .collect()
+}
 
-    /// Make a string from the character labelling the top crate of every column.
+/// Each element of the vec is a column of crates, from the bottom up. Columns may be empty.
+/// Each crate is represented by its label, which might be more than one character.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Row(Vec<Vec<String>>);
+
+impl Row {
+    /// Make a string from the label of the top crate of every column.
     fn top_of_each_column(&self) -> String {
         self.0
             .iter()
-            .map(|column| match column.last() {
-                Some(char) => String::from(*char),
-                None => Default::default(),
-            })
+            .filter_map(|column| column.last())
+            .map(String::as_str)
             .collect()
     }
 
-    /// Use the crane to rearrange crates.
-    fn move_crates(&mut self, rearrangement: &Rearrangement, reverse: bool) {
-        let mut buf = Vec::new();
-        for _ in 0..rearrangement.qty {
-            let to_move = self.0[rearrangement.src]
-                .pop()
-                .expect("not enough crates in column");
-            buf.push(to_move);
-        }
-        if reverse {
-            buf.reverse();
-        }
-        self.0[rearrangement.dst].extend(buf);
+    /// Lift the top `qty` crates off column `src` and put them on column `dst`, keeping their order.
+    /// Panics if there aren't enough crates; use `validate` to check first.
+    fn lift(&mut self, src: usize, dst: usize, qty: usize) {
+        lift(&mut self.0, src, dst, qty);
+    }
+
+    /// Like `lift`, but the crates end up in reverse order, as if moved one at a time.
+    fn lift_reversed(&mut self, src: usize, dst: usize, qty: usize) {
+        lift_reversed(&mut self.0, src, dst, qty);
     }
 }
 
+/// Lift the top `qty` items off column `src` and put them on column `dst`, keeping their order.
+/// Works on columns of anything, so crates can be tracked by something other than their label.
+fn lift<T>(columns: &mut [Vec<T>], src: usize, dst: usize, qty: usize) {
+    if let Some((from, to)) = two_columns(columns, src, dst) {
+        let start = from
+            .len()
+            .checked_sub(qty)
+            .expect("not enough crates in column");
+        to.extend(from.drain(start..));
+    }
+}
+
+/// Like `lift`, but the items end up in reverse order.
+fn lift_reversed<T>(columns: &mut [Vec<T>], src: usize, dst: usize, qty: usize) {
+    if let Some((from, to)) = two_columns(columns, src, dst) {
+        let start = from
+            .len()
+            .checked_sub(qty)
+            .expect("not enough crates in column");
+        to.extend(from.drain(start..).rev());
+    }
+}
+
+/// Borrow two different columns at once. Returns None if they're the same column,
+/// in which case moving crates between them wouldn't change anything.
+fn two_columns<T>(
+    columns: &mut [Vec<T>],
+    a: usize,
+    b: usize,
+) -> Option<(&mut Vec<T>, &mut Vec<T>)> {
+    if a == b {
+        return None;
+    }
+    let (low, high) = columns.split_at_mut(a.max(b));
+    let (low, high) = (&mut low[a.min(b)], &mut high[0]);
+    Some(if a < b { (low, high) } else { (high, low) })
+}
+
 /// Move a certain quantity of crates from column number 'src' to column number 'dst'.
 /// Uses 0-based indices for 'src' and 'dst'.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Rearrangement {
     qty: usize,
     src: usize,
     dst: usize,
 }
 
+/// Shorthand for writing procedures in tests. Columns are 0-based, like in `Rearrangement`.
+#[cfg(test)]
+fn mv(qty: usize, src: usize, dst: usize) -> Rearrangement {
+    Rearrangement { qty, src, dst }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test() {
-        let input = include_bytes!("../example");
-        let (a1, a2) = solve(input);
-        assert_eq!(a1, "CMZ");
-        assert_eq!(a2, "MCD");
+        let (row, rearrangements) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let cranes: [&dyn Crane; 3] = [&CrateMover9000, &CrateMover9001, &LimitedCrane(2)];
+        let answers: Vec<_> = solve(&row, &rearrangements, &cranes)
+            .unwrap()
+            .iter()
+            .map(Row::top_of_each_column)
+            .collect();
+        assert_eq!(answers, vec!["CMZ", "MCD", "MCZ"]);
     }
 }
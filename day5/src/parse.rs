@@ -1,10 +1,11 @@
+use std::{fmt, ops::Range};
+
 use nom::{
-    branch::alt,
-    bytes::complete::{tag, take_until},
-    character::complete::{anychar, char as onechar, line_ending, u32 as parse_u32},
-    combinator::{map, value},
-    multi::separated_list1,
-    sequence::{delimited, terminated, tuple},
+    bytes::complete::{tag, take_till1, take_until},
+    character::complete::{char as onechar, line_ending, space0, u32 as parse_u32},
+    combinator::{all_consuming, consumed, map, map_res, value, verify},
+    multi::{fold_many0, many1},
+    sequence::{delimited, pair, terminated, tuple},
     IResult,
 };
 
@@ -18,64 +19,154 @@ type Input<'a> = &'a [u8];
 /// Err branch is a nom error.
 type Result<'a, T> = IResult<&'a [u8], T>;
 
-pub fn entire_input(input: &[u8]) -> (Row, Vec<Rearrangement>) {
-    fn ignored_line(i: Input) -> Result<()> {
-        value((), terminated(take_until("\n"), line_ending))(i)
-    }
-    map(
-        tuple((
-            initial_stacked_rows,
-            ignored_line,
-            ignored_line,
-            rearrangements,
-        )),
-        |(row, _, _, rearrangements)| (row, rearrangements),
-    )(input)
-    .expect("parse error")
-    .1
+/// Why the input couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The drawing at the top, with its footer of column numbers, doesn't make sense.
+    Drawing,
+    /// A line of the procedure isn't like `move 1 from 2 to 1`, with columns numbered from 1.
+    Rearrangement { line: usize, text: String },
 }
 
-/// Parse the input describing the initial arrangement of stacked crates.
-fn initial_stacked_rows(i: Input) -> Result<Row> {
-    /// Parse one line of the input, describing an initial row of the crates.
-    fn row(i: Input) -> Result<Row> {
-        let p = separated_list1(onechar(' '), alt((parse_crate, parse_no_crate)));
-        map(p, Row)(i)
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Drawing => write!(f, "could not parse the drawing of the crates"),
+            Error::Rearrangement { line, text } => write!(
+                f,
+                "line {line}: expected a rearrangement like `move 1 from 2 to 1`, got {text:?}"
+            ),
+        }
     }
-    /// Parse a column with one crate.
-    fn parse_crate(i: Input) -> Result<Vec<char>> {
-        let p = delimited(onechar('['), anychar, onechar(']'));
-        map(p, |ch| vec![ch])(i)
-    }
-    /// Parse a column with no crates.
-    fn parse_no_crate(i: Input) -> Result<Vec<char>> {
-        // Note `value(x, p)` === `map(p, |_out| x)`
-        // Basically `value` is sugar for `map` where you don't need the output value.
-        value(Vec::new(), tag("   "))(i)
+}
+
+impl std::error::Error for Error {}
+
+/// Parse the drawing and every line of the procedure after it.
+pub fn entire_input(input: &[u8]) -> std::result::Result<(Row, Vec<Rearrangement>), Error> {
+    let (procedure, row) = terminated(drawing, ignored_line)(input).map_err(|_| Error::Drawing)?;
+    let lines_before = input[..input.len() - procedure.len()]
+        .iter()
+        .filter(|&&byte| byte == b'\n')
+        .count();
+    let procedure = procedure.strip_suffix(b"\n").unwrap_or(procedure);
+    let rearrangements = procedure
+        .split(|&byte| byte == b'\n')
+        .enumerate()
+        .map(|(i, text)| {
+            let text = text.strip_suffix(b"\r").unwrap_or(text);
+            all_consuming(rearrangement)(text)
+                .map(|(_, rearrangement)| rearrangement)
+                .map_err(|_| Error::Rearrangement {
+                    line: lines_before + i + 1,
+                    text: String::from_utf8_lossy(text).into_owned(),
+                })
+        })
+        .collect::<std::result::Result<_, _>>()?;
+    Ok((row, rearrangements))
+}
+
+fn ignored_line(i: Input) -> Result<()> {
+    value((), terminated(take_until("\n"), line_ending))(i)
+}
+
+/// Parse a drawing of stacked crates, including its footer of column numbers.
+///
+/// The footer decides how many columns there are and where each one is, so crate labels can be
+/// any width, there can be any number of columns, and lines can have their trailing spaces
+/// stripped. Each crate belongs to the column whose number is closest to the middle of it.
+pub fn drawing(i: Input) -> Result<Row> {
+    map_res(
+        pair(
+            many1(terminated(crate_line, line_ending)),
+            terminated(footer, line_ending),
+        ),
+        |(lines, columns)| stack(lines, &columns),
+    )(i)
+}
+
+/// Put each crate in its column, returning an error if two crates on one line are in the same one.
+fn stack(
+    lines: Vec<Vec<(Range<usize>, String)>>,
+    columns: &[Range<usize>],
+) -> std::result::Result<Row, &'static str> {
+    let mut row = Row(vec![Vec::new(); columns.len()]);
+    // Go from the bottom line up, so each column's crates are in order from the bottom.
+    for line in lines.into_iter().rev() {
+        let mut filled = vec![false; columns.len()];
+        for (span, label) in line {
+            let column = (0..columns.len())
+                .min_by_key(|&c| middle(&columns[c]).abs_diff(middle(&span)))
+                .ok_or("the drawing has no columns")?;
+            if filled[column] {
+                return Err("two crates on the same line are in the same column");
+            }
+            filled[column] = true;
+            row.0[column].push(label);
+        }
     }
+    Ok(row)
+}
 
-    /// Parse and stack all rows into one row with many columns of crates.
-    fn all_rows(i: Input) -> Result<Row> {
-        map(separated_list1(line_ending, row), |rows| {
-            rows.into_iter()
-                .reduce(|previous_rows, row| row.stack(previous_rows))
-                .expect("must have > 0 rows")
-        })(i)
+/// Twice the position of the middle of a span (doubled so it's always a whole number).
+fn middle(span: &Range<usize>) -> usize {
+    span.start + span.end
+}
+
+/// Parse a line of crates like `[A]     [BC]`, returning each crate's label and where it is.
+fn crate_line(i: Input) -> Result<Vec<(Range<usize>, String)>> {
+    /// Parse one crate's label.
+    fn parse_crate(i: Input) -> Result<String> {
+        let label = take_till1(|c: u8| c == b']' || c.is_ascii_whitespace());
+        map_res(
+            delimited(onechar('['), label, onechar(']')),
+            |bytes: &[u8]| String::from_utf8(bytes.to_vec()),
+        )(i)
     }
+    terminated(positioned(parse_crate), space0)(i)
+}
+
+/// Parse the footer numbering the columns, like ` 1   2   3`, returning where each number is.
+fn footer(i: Input) -> Result<Vec<Range<usize>>> {
+    let numbers = verify(
+        positioned(parse_u32),
+        |numbers: &[(Range<usize>, u32)]| {
+            !numbers.is_empty() && numbers.iter().zip(1..).all(|((_, n), i)| *n == i)
+        },
+    );
+    map(terminated(numbers, space0), |numbers| {
+        numbers.into_iter().map(|(span, _)| span).collect()
+    })(i)
+}
 
-    terminated(all_rows, onechar('\n'))(i)
+/// Parse a sequence of items separated by spaces, also returning the byte range each item
+/// was found at, counting from the start of the sequence.
+fn positioned<'a, O>(
+    item: impl FnMut(Input<'a>) -> Result<'a, O>,
+) -> impl FnMut(Input<'a>) -> Result<'a, Vec<(Range<usize>, O)>> {
+    fold_many0(
+        pair(space0, consumed(item)),
+        Vec::new,
+        |mut items: Vec<(Range<usize>, O)>, (spaces, (text, item))| {
+            let start = items.last().map_or(0, |(span, _)| span.end) + spaces.len();
+            items.push((start..start + text.len(), item));
+            items
+        },
+    )
 }
 
-/// Parse all crane rearrangements.
-fn rearrangements(i: Input) -> Result<Vec<Rearrangement>> {
-    let parse_rearrangement = map(
+/// Parse one crane rearrangement.
+fn rearrangement(i: Input) -> Result<Rearrangement> {
+    // Columns are numbered from 1 in the input, so there's no column 0.
+    let column = || verify(parse_u32, |&column| column > 0);
+    map(
         tuple((
             tag("move "),
             parse_u32,
             tag(" from "),
-            parse_u32,
+            column(),
             tag(" to "),
-            parse_u32,
+            column(),
         )),
         |(_, qty, _, src, _, dst)| Rearrangement {
             qty: qty as _,
@@ -83,6 +174,90 @@ fn rearrangements(i: Input) -> Result<Vec<Rearrangement>> {
             src: (src - 1) as _,
             dst: (dst - 1) as _,
         },
-    );
-    separated_list1(line_ending, parse_rearrangement)(i)
+    )(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(columns: &[&[&str]]) -> Row {
+        Row(columns
+            .iter()
+            .map(|column| column.iter().map(|label| label.to_string()).collect())
+            .collect())
+    }
+
+    #[test]
+    fn test_example() {
+        let (row_parsed, rearrangements) = entire_input(include_bytes!("../example")).unwrap();
+        assert_eq!(row_parsed, row(&[&["Z", "N"], &["M", "C", "D"], &["P"]]));
+        assert_eq!(rearrangements.len(), 4);
+    }
+
+    #[test]
+    fn test_trailing_spaces_stripped() {
+        let drawing = b"    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3\n";
+        let (_, parsed) = super::drawing(drawing).unwrap();
+        assert_eq!(parsed, row(&[&["Z", "N"], &["M", "C", "D"], &["P"]]));
+    }
+
+    #[test]
+    fn test_wide_labels() {
+        let drawing = b"       [Ab]\n[XYZ]  [C]  [Q]\n  1     2    3\n";
+        let (_, parsed) = super::drawing(drawing).unwrap();
+        assert_eq!(parsed, row(&[&["XYZ"], &["C", "Ab"], &["Q"]]));
+    }
+
+    #[test]
+    fn test_many_columns() {
+        let input = b"[A]                                         [L]\n \
+            1   2   3   4   5   6   7   8   9   10  11  12\n\
+            \n\
+            move 1 from 12 to 10";
+        let (parsed, rearrangements) = entire_input(input).unwrap();
+        assert_eq!(parsed.0.len(), 12);
+        assert_eq!(parsed.0[0], vec!["A"]);
+        assert_eq!(parsed.0[11], vec!["L"]);
+        assert_eq!(
+            rearrangements,
+            vec![Rearrangement {
+                qty: 1,
+                src: 11,
+                dst: 9
+            }]
+        );
+    }
+
+    #[test]
+    fn test_bad_drawings() {
+        // Footer numbers out of order.
+        assert!(super::drawing(b"[A] [B]\n 2   1\n").is_err());
+        // Two crates closest to the same column.
+        assert!(super::drawing(b"[A][B]\n 1      2\n").is_err());
+    }
+
+    #[test]
+    fn test_bad_procedures() {
+        let drawing = "[A] [B]\n 1   2\n\n";
+        let error =
+            |procedure: &str| entire_input(format!("{drawing}{procedure}").as_bytes()).unwrap_err();
+        assert_eq!(
+            error("move 1 from 0 to 1\n"),
+            Error::Rearrangement {
+                line: 4,
+                text: "move 1 from 0 to 1".to_owned()
+            }
+        );
+        // Lines after a bad one aren't quietly dropped.
+        assert_eq!(
+            error("move 1 from 1 to 2\nmove one from 2 to 1\nmove 1 from 2 to 1"),
+            Error::Rearrangement {
+                line: 5,
+                text: "move one from 2 to 1".to_owned()
+            }
+        );
+        assert_eq!(error("move 1 from 1 to 0"), error("move 1 from 1 to 0\n"));
+        assert_eq!(entire_input(b"[A][B]\n 1      2\n\n"), Err(Error::Drawing));
+    }
 }
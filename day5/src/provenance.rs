@@ -0,0 +1,194 @@
+use super::{
+    crane::{move_in_lifts, Crane},
+    Rearrangement, Row,
+};
+
+/// One time a crate was moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    /// 0-based index of the rearrangement in the procedure.
+    pub step: usize,
+    /// 0-based column numbers.
+    pub from: usize,
+    pub to: usize,
+}
+
+/// Everything known about one crate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crate {
+    pub label: String,
+    /// The (column, height) the crate started at. Heights start from 0 at the bottom.
+    pub origin: (usize, usize),
+    /// Every move the crate took part in, in order.
+    pub moves: Vec<Move>,
+}
+
+/// Tracks every crate individually as the procedure is carried out, so crates with the same
+/// label can be told apart and their history looked up.
+///
+/// Moves crates the same way the crane does, by lifting up to its capacity at a time,
+/// so cranes which override `Crane::rearrange` with something different can't be tracked.
+pub struct Provenance {
+    /// Same shape as the row, but holding indices into `crates` instead of labels.
+    columns: Vec<Vec<usize>>,
+    crates: Vec<Crate>,
+    steps: usize,
+}
+
+impl Provenance {
+    /// Start tracking the crates in this row.
+    pub fn new(row: &Row) -> Self {
+        let mut crates = Vec::new();
+        let columns = row
+            .0
+            .iter()
+            .enumerate()
+            .map(|(column, labels)| {
+                labels
+                    .iter()
+                    .enumerate()
+                    .map(|(height, label)| {
+                        crates.push(Crate {
+                            label: label.clone(),
+                            origin: (column, height),
+                            moves: Vec::new(),
+                        });
+                        crates.len() - 1
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            columns,
+            crates,
+            steps: 0,
+        }
+    }
+
+    /// Track the crates through the whole procedure.
+    pub fn run(crane: &dyn Crane, row: &Row, rearrangements: &[Rearrangement]) -> Self {
+        let mut provenance = Self::new(row);
+        for rearrangement in rearrangements {
+            provenance.rearrange(crane, rearrangement);
+        }
+        provenance
+    }
+
+    /// Carry out the next rearrangement, recording the move against every crate involved.
+    pub fn rearrange(&mut self, crane: &dyn Crane, rearrangement: &Rearrangement) {
+        let &Rearrangement { qty, src, dst } = rearrangement;
+        if src == dst {
+            // Nothing actually moves, so there's nothing to record.
+            self.steps += 1;
+            return;
+        }
+        move_in_lifts(&mut self.columns, crane.capacity(), rearrangement);
+        let column = &self.columns[dst];
+        for &id in &column[column.len() - qty..] {
+            self.crates[id].moves.push(Move {
+                step: self.steps,
+                from: src,
+                to: dst,
+            });
+        }
+        self.steps += 1;
+    }
+
+    /// The row of crates as it is now.
+    pub fn row(&self) -> Row {
+        Row(self
+            .columns
+            .iter()
+            .map(|column| {
+                column
+                    .iter()
+                    .map(|&id| self.crates[id].label.clone())
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// The crate now on top of this (0-based) column.
+    pub fn top(&self, column: usize) -> Option<&Crate> {
+        let id = self.columns.get(column)?.last()?;
+        Some(&self.crates[*id])
+    }
+
+    /// Every crate which hasn't moved at all.
+    pub fn never_moved(&self) -> impl Iterator<Item = &Crate> {
+        self.crates.iter().filter(|c| c.moves.is_empty())
+    }
+
+    /// The crate which has moved the most times.
+    /// If there's a tie, picks the first one, counting up each column from the left.
+    pub fn most_moved(&self) -> Option<&Crate> {
+        self.crates.iter().rev().max_by_key(|c| c.moves.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crane::{CrateMover9000, CrateMover9001, LimitedCrane},
+        mv, parse,
+    };
+
+    #[test]
+    fn test_matches_crane() {
+        let (row, procedure) = parse::entire_input(include_bytes!("../input")).unwrap();
+        for crane in [
+            &CrateMover9000 as &dyn Crane,
+            &CrateMover9001,
+            &LimitedCrane(4),
+        ] {
+            let mut expected = row.clone();
+            for rearrangement in &procedure {
+                crane.rearrange(&mut expected, rearrangement);
+            }
+            assert_eq!(Provenance::run(crane, &row, &procedure).row(), expected);
+        }
+    }
+
+    #[test]
+    fn test_example_history() {
+        let (row, procedure) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let provenance = Provenance::run(&CrateMover9000, &row, &procedure);
+
+        // Z ends up on top of column 3, after moving there in step 2.
+        let top = provenance.top(2).unwrap();
+        assert_eq!(top.label, "Z");
+        assert_eq!(top.origin, (0, 0));
+        assert_eq!(
+            top.moves,
+            vec![Move {
+                step: 1,
+                from: 0,
+                to: 2
+            }]
+        );
+
+        let never_moved: Vec<_> = provenance.never_moved().map(|c| &c.label).collect();
+        assert_eq!(never_moved, vec!["P"]);
+
+        // D moves in steps 1 and 2, M in steps 3 and 4. M is lower down in the initial row.
+        let most_moved = provenance.most_moved().unwrap();
+        assert_eq!(most_moved.label, "M");
+        assert_eq!(most_moved.moves.len(), 2);
+        assert!(provenance.top(3).is_none());
+    }
+
+    #[test]
+    fn test_move_onto_same_column() {
+        let (row, _) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let procedure = [mv(2, 0, 0), mv(1, 1, 2)];
+        let provenance = Provenance::run(&CrateMover9000, &row, &procedure);
+        let mut expected = row.clone();
+        expected.lift(1, 2, 1);
+        assert_eq!(provenance.row(), expected);
+        assert_eq!(provenance.most_moved().unwrap().label, "D");
+        assert_eq!(provenance.top(2).unwrap().moves[0].step, 1);
+        let never_moved: Vec<_> = provenance.never_moved().map(|c| &c.label).collect();
+        assert_eq!(never_moved, ["Z", "N", "M", "C", "P"]);
+    }
+}
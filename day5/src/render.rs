@@ -0,0 +1,124 @@
+use std::fmt;
+
+use super::{Rearrangement, Row};
+
+/// Draw the row the same way the problem input does, e.g.
+/// ```text
+///     [D]    
+/// [N] [C]    
+/// [Z] [M] [P]
+///  1   2   3
+/// ```
+impl fmt::Display for Row {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        draw(f, self, &[], Highlight::Ansi)
+    }
+}
+
+/// How to make some crates stand out in a drawing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    /// Show the crate in inverted colours, for terminals.
+    Ansi,
+    /// Draw the crate as `{X}` instead of `[X]`, for plain text.
+    Braces,
+}
+
+/// Draw the row like the problem input does, highlighting the crates at the given
+/// (column, height) positions. Heights start from 0 at the bottom of each column.
+/// Every column is as wide as the widest crate, so labels of any width line up.
+pub fn draw(
+    f: &mut impl fmt::Write,
+    row: &Row,
+    highlighted: &[(usize, usize)],
+    highlight: Highlight,
+) -> fmt::Result {
+    let height = row.0.iter().map(Vec::len).max().unwrap_or_default();
+    let label_width = row.0.iter().flatten().map(|label| label.chars().count());
+    // Leave room for the brackets.
+    let width = label_width.max().unwrap_or(1) + 2;
+    for level in (0..height).rev() {
+        let line: Vec<_> = row
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, column)| match column.get(level) {
+                Some(label) if highlighted.contains(&(i, level)) => match highlight {
+                    Highlight::Ansi => {
+                        let cell = format!("{:^width$}", format!("[{label}]"));
+                        format!("\x1b[7m{cell}\x1b[0m")
+                    }
+                    Highlight::Braces => format!("{:^width$}", format!("{{{label}}}")),
+                },
+                Some(label) => format!("{:^width$}", format!("[{label}]")),
+                None => " ".repeat(width),
+            })
+            .collect();
+        writeln!(f, "{}", line.join(" "))?;
+    }
+    let footer: Vec<_> = (1..=row.0.len()).map(|n| format!("{n:^width$}")).collect();
+    // Like the puzzle input, the footer has no trailing whitespace.
+    writeln!(f, "{}", footer.join(" ").trim_end())
+}
+
+/// Write the rearrangement the same way the problem input does, e.g. `move 1 from 2 to 1`.
+impl fmt::Display for Rearrangement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Convert back to the input format's 1-based indices.
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.qty,
+            self.src + 1,
+            self.dst + 1
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        crane::{Crane, CrateMover9001},
+        parse,
+    };
+
+    #[test]
+    fn test_render_example() {
+        let (row, _) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let expected = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3\n";
+        assert_eq!(row.to_string(), expected);
+    }
+
+    #[test]
+    fn test_render_wide_labels() {
+        let (_, row) = parse::drawing(b"       [Ab]\n[XYZ]  [C]  [Q]\n  1     2    3\n").unwrap();
+        let expected = "      [Ab]       \n[XYZ]  [C]   [Q] \n  1     2     3\n";
+        assert_eq!(row.to_string(), expected);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let (row, rearrangements) = parse::entire_input(include_bytes!("../input")).unwrap();
+        assert_eq!(parse::drawing(row.to_string().as_bytes()).unwrap().1, row);
+
+        let wide = parse::drawing(b"       [Ab]\n[XYZ]  [C]  [Q]\n  1     2    3\n")
+            .unwrap()
+            .1;
+        assert_eq!(parse::drawing(wide.to_string().as_bytes()).unwrap().1, wide);
+        let mut many_columns = row.clone();
+        many_columns.0.extend(row.0.iter().cloned());
+        assert_eq!(
+            parse::drawing(many_columns.to_string().as_bytes())
+                .unwrap()
+                .1,
+            many_columns
+        );
+
+        // Also check rows where the columns are very different heights, and some are empty.
+        let mut row = row;
+        for rearrangement in rearrangements.iter().take(50) {
+            CrateMover9001.rearrange(&mut row, rearrangement);
+            assert_eq!(parse::drawing(row.to_string().as_bytes()).unwrap().1, row);
+        }
+    }
+}
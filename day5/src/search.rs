@@ -0,0 +1,139 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{crane::Crane, Rearrangement, Row};
+
+/// What the crates should look like at the end of the procedure.
+pub enum Goal {
+    /// Every crate in a particular place.
+    Row(Row),
+    /// Particular crates on top of each column, like the answer to Q1 and Q2.
+    Tops(String),
+}
+
+impl Goal {
+    fn is_met(&self, row: &Row) -> bool {
+        match self {
+            Self::Row(goal) => goal == row,
+            Self::Tops(tops) => &row.top_of_each_column() == tops,
+        }
+    }
+}
+
+/// Find a procedure with the fewest possible rearrangements which reaches the goal, using the
+/// given crane. This is a breadth-first search over every arrangement of the crates, so it's
+/// only practical for small rows. Gives up (returning None) after visiting `max_states`
+/// arrangements, or if there's no way to reach the goal.
+pub fn shortest_procedure(
+    crane: &dyn Crane,
+    start: &Row,
+    goal: &Goal,
+    max_states: usize,
+) -> Option<Vec<Rearrangement>> {
+    if let Goal::Row(goal) = goal {
+        // Crates can't be created or destroyed.
+        let mut have: Vec<&String> = start.0.iter().flatten().collect();
+        let mut want: Vec<&String> = goal.0.iter().flatten().collect();
+        have.sort_unstable();
+        want.sort_unstable();
+        if have != want || start.0.len() != goal.0.len() {
+            return None;
+        }
+    }
+
+    // Each arrangement seen so far, with the index of the arrangement it was reached from,
+    // and the rearrangement that got there.
+    let mut seen: Vec<(Row, Option<(usize, Rearrangement)>)> = vec![(start.clone(), None)];
+    let mut visited: HashSet<Row> = HashSet::from([start.clone()]);
+    let mut queue = VecDeque::from([0]);
+    while let Some(i) = queue.pop_front() {
+        if goal.is_met(&seen[i].0) {
+            let mut procedure = Vec::new();
+            let mut current = i;
+            while let Some((parent, rearrangement)) = &seen[current].1 {
+                procedure.push(rearrangement.clone());
+                current = *parent;
+            }
+            procedure.reverse();
+            return Some(procedure);
+        }
+        let columns = seen[i].0 .0.len();
+        for src in 0..columns {
+            for dst in (0..columns).filter(|&dst| dst != src) {
+                for qty in 1..=seen[i].0 .0[src].len() {
+                    let rearrangement = Rearrangement { qty, src, dst };
+                    let mut next = seen[i].0.clone();
+                    crane.rearrange(&mut next, &rearrangement);
+                    if visited.contains(&next) {
+                        continue;
+                    }
+                    if visited.len() >= max_states {
+                        return None;
+                    }
+                    visited.insert(next.clone());
+                    seen.push((next, Some((i, rearrangement))));
+                    queue.push_back(seen.len() - 1);
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crane::{CrateMover9000, CrateMover9001},
+        parse, validate,
+    };
+
+    const MAX_STATES: usize = 100_000;
+
+    fn run(crane: &dyn Crane, row: &Row, procedure: &[Rearrangement]) -> Row {
+        let mut row = row.clone();
+        validate::run(crane, &mut row, procedure).unwrap();
+        row
+    }
+
+    #[test]
+    fn test_reach_example_answers() {
+        let (row, rearrangements) = parse::entire_input(include_bytes!("../example")).unwrap();
+        for (crane, tops) in [
+            (&CrateMover9000 as &dyn Crane, "CMZ"),
+            (&CrateMover9001, "MCD"),
+        ] {
+            let goal = Goal::Tops(tops.to_owned());
+            let procedure = shortest_procedure(crane, &row, &goal, MAX_STATES).unwrap();
+            assert!(procedure.len() <= rearrangements.len());
+            assert!(goal.is_met(&run(crane, &row, &procedure)));
+        }
+    }
+
+    #[test]
+    fn test_reach_row() {
+        let (row, rearrangements) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let target = run(&CrateMover9001, &row, &rearrangements);
+        let goal = Goal::Row(target.clone());
+        let procedure = shortest_procedure(&CrateMover9001, &row, &goal, MAX_STATES).unwrap();
+        assert_eq!(run(&CrateMover9001, &row, &procedure), target);
+        // The example's own procedure is already as short as possible.
+        assert_eq!(procedure.len(), rearrangements.len());
+    }
+
+    #[test]
+    fn test_already_there() {
+        let (row, _) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let goal = Goal::Tops("NDP".to_owned());
+        let procedure = shortest_procedure(&CrateMover9000, &row, &goal, MAX_STATES).unwrap();
+        assert!(procedure.is_empty());
+    }
+
+    #[test]
+    fn test_impossible() {
+        let (row, _) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let goal = Goal::Tops("XYZ".to_owned());
+        assert!(shortest_procedure(&CrateMover9000, &row, &goal, MAX_STATES).is_none());
+        let goal = Goal::Row(Row(vec![vec!["A".to_owned()], vec![], vec![]]));
+        assert!(shortest_procedure(&CrateMover9000, &row, &goal, MAX_STATES).is_none());
+    }
+}
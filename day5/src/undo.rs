@@ -0,0 +1,160 @@
+use super::{
+    crane::{self, Crane},
+    validate::{self, StepError},
+    Rearrangement, Row,
+};
+
+/// A rearrangement that was carried out, with enough information to undo it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Applied {
+    rearrangement: Rearrangement,
+    /// Capacity of the crane that did it, which determines how the crates were split into lifts.
+    capacity: usize,
+}
+
+/// Every rearrangement applied to a row, so they can be undone and redone.
+#[derive(Debug, Default)]
+pub struct UndoLog {
+    done: Vec<Applied>,
+    undone: Vec<Applied>,
+}
+
+impl Row {
+    /// Use the crane to rearrange crates, recording it in the log so it can be undone.
+    /// Anything previously undone can no longer be redone.
+    pub fn apply_logged(
+        &mut self,
+        crane: &dyn Crane,
+        rearrangement: &Rearrangement,
+        log: &mut UndoLog,
+    ) -> Result<(), StepError> {
+        validate::check_step(self, log.done.len(), rearrangement)?;
+        crane.rearrange(self, rearrangement);
+        log.done.push(Applied {
+            rearrangement: rearrangement.clone(),
+            capacity: crane.capacity(),
+        });
+        log.undone.clear();
+        Ok(())
+    }
+
+    /// Put back the crates moved by the last rearrangement in the log, returning it.
+    /// Returns None if there's nothing left to undo.
+    pub fn undo(&mut self, log: &mut UndoLog) -> Option<Rearrangement> {
+        let applied = log.done.pop()?;
+        let Rearrangement { qty, src, dst } = applied.rearrangement;
+        // The crane moved full lifts first, then whatever was left over.
+        // So put back the leftover lift first, then the full ones.
+        let capacity = applied.capacity;
+        if capacity == 1 {
+            // Moving one at a time just reverses the crates, which can be done in one go.
+            self.lift_reversed(dst, src, qty);
+        } else {
+            let leftover = qty % capacity;
+            if leftover > 0 {
+                self.lift(dst, src, leftover);
+            }
+            for _ in 0..qty / capacity {
+                self.lift(dst, src, capacity);
+            }
+        }
+        let rearrangement = applied.rearrangement.clone();
+        log.undone.push(applied);
+        Some(rearrangement)
+    }
+
+    /// Carry out the last rearrangement that was undone again, returning it.
+    /// Returns None if there's nothing to redo.
+    pub fn redo(&mut self, log: &mut UndoLog) -> Option<Rearrangement> {
+        let applied = log.undone.pop()?;
+        crane::move_in_lifts(&mut self.0, applied.capacity, &applied.rearrangement);
+        let rearrangement = applied.rearrangement.clone();
+        log.done.push(applied);
+        Some(rearrangement)
+    }
+}
+
+impl Rearrangement {
+    /// Move the same crates back again.
+    pub fn inverse(&self) -> Self {
+        Self {
+            qty: self.qty,
+            src: self.dst,
+            dst: self.src,
+        }
+    }
+}
+
+/// The procedure which takes the final row of crates back to the initial one.
+///
+/// Only correct for cranes which lift either one crate at a time (like the CrateMover 9000,
+/// whose inverse reverses the crates again) or all the crates at once (like the CrateMover 9001).
+/// Cranes which split moves into several bigger lifts need an `UndoLog` instead.
+pub fn inverse_procedure(procedure: &[Rearrangement]) -> Vec<Rearrangement> {
+    procedure.iter().rev().map(Rearrangement::inverse).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        crane::{CrateMover9000, CrateMover9001, LimitedCrane},
+        parse,
+    };
+
+    #[test]
+    fn test_inverse_procedure() {
+        let (initial, procedure) = parse::entire_input(include_bytes!("../input")).unwrap();
+        for crane in [&CrateMover9000 as &dyn Crane, &CrateMover9001] {
+            let mut row = initial.clone();
+            validate::run(crane, &mut row, &procedure).unwrap();
+            assert_ne!(row, initial);
+            validate::run(crane, &mut row, &inverse_procedure(&procedure)).unwrap();
+            assert_eq!(row, initial);
+        }
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let (initial, procedure) = parse::entire_input(include_bytes!("../input")).unwrap();
+        for crane in [
+            &CrateMover9000 as &dyn Crane,
+            &CrateMover9001,
+            &LimitedCrane(3),
+        ] {
+            let mut log = UndoLog::default();
+            let mut row = initial.clone();
+            let mut states = vec![row.clone()];
+            for rearrangement in &procedure {
+                row.apply_logged(crane, rearrangement, &mut log).unwrap();
+                states.push(row.clone());
+            }
+            // Rewind halfway, checking each state along the way.
+            for (i, rearrangement) in procedure.iter().enumerate().rev().take(250) {
+                assert_eq!(row.undo(&mut log).as_ref(), Some(rearrangement));
+                assert_eq!(row, states[i]);
+            }
+            // Then go forward a bit.
+            for i in 0..10 {
+                row.redo(&mut log).unwrap();
+                assert_eq!(row, states[procedure.len() - 250 + i + 1]);
+            }
+            // Then rewind all the way.
+            while row.undo(&mut log).is_some() {}
+            assert_eq!(row, initial);
+            assert_eq!(row.redo(&mut log).as_ref(), Some(&procedure[0]));
+        }
+    }
+
+    #[test]
+    fn test_apply_clears_redo() {
+        let (mut row, procedure) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let mut log = UndoLog::default();
+        row.apply_logged(&CrateMover9001, &procedure[0], &mut log)
+            .unwrap();
+        row.undo(&mut log).unwrap();
+        row.apply_logged(&CrateMover9001, &procedure[0].inverse(), &mut log)
+            .unwrap();
+        assert!(row.redo(&mut log).is_none());
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+
+use super::{crane::Crane, Rearrangement, Row};
+
+/// A rearrangement which can't be carried out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepError {
+    /// 0-based index of the rearrangement in the procedure.
+    pub step: usize,
+    pub rearrangement: Rearrangement,
+    pub problem: Problem,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The row doesn't have this (0-based) column.
+    NoSuchColumn { column: usize },
+    /// The column doesn't have as many crates as the rearrangement moves.
+    NotEnoughCrates { column: usize, available: usize },
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {} ({}): ", self.step + 1, self.rearrangement)?;
+        match self.problem {
+            Problem::NoSuchColumn { column } => write!(f, "there is no column {}", column + 1),
+            Problem::NotEnoughCrates { column, available } => write!(
+                f,
+                "column {} only has {available} crates, {} short",
+                column + 1,
+                self.rearrangement.qty - available
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StepError {}
+
+/// Check a rearrangement could be carried out, given how many crates are in each column.
+fn check(heights: &[usize], step: usize, rearrangement: &Rearrangement) -> Result<(), StepError> {
+    let err = |problem| StepError {
+        step,
+        rearrangement: rearrangement.clone(),
+        problem,
+    };
+    let &Rearrangement { qty, src, dst } = rearrangement;
+    for column in [src, dst] {
+        if column >= heights.len() {
+            return Err(err(Problem::NoSuchColumn { column }));
+        }
+    }
+    let available = heights[src];
+    if available < qty {
+        return Err(err(Problem::NotEnoughCrates {
+            column: src,
+            available,
+        }));
+    }
+    Ok(())
+}
+
+/// Check a single rearrangement could be carried out on the row as it is now.
+pub fn check_step(row: &Row, step: usize, rearrangement: &Rearrangement) -> Result<(), StepError> {
+    let heights: Vec<usize> = row.0.iter().map(Vec::len).collect();
+    check(&heights, step, rearrangement)
+}
+
+/// Carry out the procedure, checking each rearrangement before it happens.
+/// If one can't be carried out, stops there, leaving the row as it was before that step.
+pub fn run(
+    crane: &dyn Crane,
+    row: &mut Row,
+    rearrangements: &[Rearrangement],
+) -> Result<(), StepError> {
+    let mut heights: Vec<usize> = row.0.iter().map(Vec::len).collect();
+    for (step, rearrangement) in rearrangements.iter().enumerate() {
+        check(&heights, step, rearrangement)?;
+        crane.rearrange(row, rearrangement);
+        heights[rearrangement.src] -= rearrangement.qty;
+        heights[rearrangement.dst] += rearrangement.qty;
+    }
+    Ok(())
+}
+
+/// Find every rearrangement in the procedure which can't be carried out, without changing the row.
+/// Invalid rearrangements are skipped, and checking carries on from the state before them.
+/// Which crane is used doesn't matter, because it only changes the order crates end up in.
+pub fn dry_run(row: &Row, rearrangements: &[Rearrangement]) -> Vec<StepError> {
+    let mut heights: Vec<usize> = row.0.iter().map(Vec::len).collect();
+    let mut errors = Vec::new();
+    for (step, rearrangement) in rearrangements.iter().enumerate() {
+        match check(&heights, step, rearrangement) {
+            Ok(()) => {
+                heights[rearrangement.src] -= rearrangement.qty;
+                heights[rearrangement.dst] += rearrangement.qty;
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{crane::CrateMover9001, mv, parse};
+
+    #[test]
+    fn test_valid_input() {
+        let (row, rearrangements) = parse::entire_input(include_bytes!("../input")).unwrap();
+        assert!(dry_run(&row, &rearrangements).is_empty());
+    }
+
+    #[test]
+    fn test_dry_run_reports_everything() {
+        let (row, _) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let before = row.clone();
+        let procedure = [mv(3, 0, 2), mv(1, 1, 0), mv(1, 3, 0), mv(4, 1, 2)];
+        let errors = dry_run(&row, &procedure);
+        assert_eq!(row, before);
+        let messages: Vec<_> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "step 1 (move 3 from 1 to 3): column 1 only has 2 crates, 1 short",
+                "step 3 (move 1 from 4 to 1): there is no column 4",
+                // Step 2 moved a crate off column 2, so it only has 2 left.
+                "step 4 (move 4 from 2 to 3): column 2 only has 2 crates, 2 short",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_stops_at_bad_step() {
+        let (mut row, _) = parse::entire_input(include_bytes!("../example")).unwrap();
+        let procedure = [mv(1, 1, 0), mv(9, 0, 1), mv(1, 0, 1)];
+        let err = run(&CrateMover9001, &mut row, &procedure).unwrap_err();
+        assert_eq!(err.step, 1);
+        assert_eq!(
+            err.problem,
+            Problem::NotEnoughCrates {
+                column: 0,
+                available: 3
+            }
+        );
+        // The first step happened, but nothing after it.
+        assert_eq!(row.top_of_each_column(), "DCP");
+    }
+}
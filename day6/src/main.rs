@@ -1,18 +1,130 @@
-use std::collections::HashSet;
+use std::{
+    fs::File,
+    io::{self, Read},
+    process,
+};
 
+mod protocol;
+mod scan;
+mod stream;
+
+/// Usage:
+///   day6
+///     Solve Q1 and Q2 using the puzzle input.
+///   day6 <file>
+///     Find every marker in a file, or in standard input if the file is `-`,
+///     reading it a little at a time.
+///   day6 bytes|words <n> <criterion> <file>
+///     Find the first marker of `n` bytes, or of `n` whitespace-separated words, in a file or
+///     standard input. The criterion is `distinct`, `repeats=K` (at most K repeats) or
+///     `no-adjacent` (no symbol the same as the one before it).
 fn main() {
-    let input: Vec<char> = include_str!("../input").chars().collect();
-    println!("Q1: {}", solve(&input, 4));
-    println!("Q2: {}", solve(&input, 14));
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if let [symbols @ ("bytes" | "words"), n, criterion, path] = &args[..] {
+        let n: usize = n.parse().unwrap_or_else(|_| usage());
+        let criterion: stream::Criterion = criterion.parse().unwrap_or_else(|e| {
+            eprintln!("{e}");
+            usage()
+        });
+        let found = if *symbols == "bytes" {
+            open(path)
+                .and_then(|reader| stream::find_marker_with(reader, n, criterion))
+                .expect("could not read the datastream")
+                .map_err(|e| e.to_string())
+        } else {
+            let mut text = String::new();
+            open(path)
+                .and_then(|mut reader| reader.read_to_string(&mut text))
+                .expect("could not read the datastream");
+            stream::find_symbol_marker(text.split_whitespace(), n, criterion)
+                .map_err(|e| e.to_string())
+        };
+        match found {
+            Ok(position) => println!("marker ends after {position} {symbols}"),
+            Err(e) => println!("{e}"),
+        }
+        return;
+    }
+    let Some(path) = args.first() else {
+        let input = include_bytes!("../input");
+        for (question, n) in [("Q1", 4), ("Q2", 14)] {
+            match solve(input, n) {
+                Ok(answer) => println!("{question}: {answer}"),
+                Err(e) => println!("{question}: {e}"),
+            }
+        }
+        print_scan(&scan::scan(&input[..], &[4, 14]).expect("reading from memory can't fail"));
+        match protocol::decode(input) {
+            Ok(decoded) => print_decoded(&decoded),
+            Err(e) => println!("could not decode the datastream: {e}"),
+        }
+        return;
+    };
+    if args.len() > 1 {
+        usage();
+    }
+    let scanned = open(path).and_then(|reader| scan::scan(reader, &[4, 14]));
+    print_scan(&scanned.expect("could not read the datastream"));
+}
+
+/// Open a file, or standard input if the path is `-`.
+fn open(path: &str) -> io::Result<Box<dyn Read>> {
+    Ok(if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        Box::new(File::open(path)?)
+    })
+}
+
+fn usage() -> ! {
+    eprintln!("usage: day6 [<file> | bytes|words <n> <criterion> <file>]");
+    process::exit(2)
+}
+
+fn print_scan(scan: &scan::Scan) {
+    for markers in &scan.markers {
+        match markers.first() {
+            Some(first) => println!(
+                "{} markers of length {}, the first ending after {first} characters",
+                markers.count(),
+                markers.len
+            ),
+            None => println!("no markers of length {}", markers.len),
+        }
+    }
+    let longest = &scan.longest_distinct;
+    println!(
+        "longest run of different characters: {} characters, from {} to {}",
+        longest.len(),
+        longest.start,
+        longest.end
+    );
+}
+
+fn print_decoded(decoded: &protocol::Decoded) {
+    println!("{} bytes of preamble", decoded.preamble.len());
+    let packet = &decoded.packet;
+    println!(
+        "packet at {}: marker {}, {} bytes of header",
+        packet.offset,
+        String::from_utf8_lossy(packet.marker),
+        packet.header.len()
+    );
+    for message in &decoded.messages {
+        println!(
+            "message at {}: marker {}, {} bytes of payload from {}",
+            message.offset,
+            String::from_utf8_lossy(message.marker),
+            message.payload.len(),
+            message.payload_offset()
+        );
+    }
 }
 
-fn solve(datastream: &[char], n: usize) -> usize {
-    (n..=datastream.len())
-        .find(|i| {
-            let num_unique_chars = datastream[i - n..*i].iter().collect::<HashSet<_>>().len();
-            num_unique_chars == n
-        })
-        .unwrap()
+/// How many characters have to be read to find the first `n` characters which are all different?
+fn solve(datastream: &[u8], n: usize) -> Result<usize, stream::NoMarker> {
+    stream::find_marker(datastream, n).expect("reading from memory can't fail")
 }
 
 #[cfg(test)]
@@ -21,13 +133,26 @@ mod tests {
 
     #[test]
     fn test_q1() {
-        let input: Vec<char> = include_str!("../input").chars().collect();
-        assert_eq!(1802, solve(&input, 4));
+        let input = include_bytes!("../input");
+        assert_eq!(Ok(1802), solve(input, 4));
     }
 
     #[test]
     fn test_q2() {
-        let input: Vec<char> = include_str!("../input").chars().collect();
-        assert_eq!(3551, solve(&input, 14));
+        let input = include_bytes!("../input");
+        assert_eq!(Ok(3551), solve(input, 14));
+    }
+
+    #[test]
+    fn test_no_marker() {
+        assert_eq!(
+            solve(b"mjqjpqm", 14),
+            Err(stream::NoMarker::TooShort { len: 7, n: 14 })
+        );
+        assert_eq!(solve(b"mjqjpqm", 0), Err(stream::NoMarker::ZeroLength));
+        assert!(matches!(
+            solve(b"mjqjpqm", 5),
+            Err(stream::NoMarker::NotFound { duplicates: 1, .. })
+        ));
     }
 }
@@ -0,0 +1,181 @@
+use std::fmt;
+
+use crate::stream;
+
+/// How many different characters start a packet.
+pub const PACKET_MARKER: usize = 4;
+/// How many different characters start a message.
+pub const MESSAGE_MARKER: usize = 14;
+
+/// The start of a packet: its marker, and the header bytes after it up to the first message.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Packet<'a> {
+    /// Where the marker starts in the datastream.
+    pub offset: usize,
+    pub marker: &'a [u8],
+    pub header: &'a [u8],
+}
+
+/// A message: its marker, and the payload bytes after it up to the next message or the end.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Message<'a> {
+    /// Where the marker starts in the datastream.
+    pub offset: usize,
+    pub marker: &'a [u8],
+    pub payload: &'a [u8],
+}
+
+impl Message<'_> {
+    /// Where the payload starts in the datastream.
+    pub fn payload_offset(&self) -> usize {
+        self.offset + self.marker.len()
+    }
+}
+
+/// A datastream split into its parts.
+///
+/// A datastream holds exactly one packet. Four different characters in a row turn up all
+/// the time in the middle of a header or payload, so only the first start-of-packet marker
+/// counts; any later ones are just part of the data.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Decoded<'a> {
+    /// Noise from the device before the packet starts.
+    pub preamble: &'a [u8],
+    pub packet: Packet<'a>,
+    pub messages: Vec<Message<'a>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FramingError {
+    /// There's no start-of-packet marker anywhere.
+    NoPacket,
+    /// The packet starts at this offset but no start-of-message marker follows it.
+    NoMessage { packet: usize },
+}
+
+impl fmt::Display for FramingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FramingError::NoPacket => write!(f, "no start-of-packet marker"),
+            FramingError::NoMessage { packet } => write!(
+                f,
+                "the packet starting at {packet} has no start-of-message marker"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FramingError {}
+
+/// Split a datastream into a packet and its messages.
+///
+/// The packet starts at the first start-of-packet marker, and later ones are ignored.
+/// Each message starts at the next start-of-message marker lying entirely after the previous
+/// marker, so markers never share bytes. Whatever comes between two markers belongs to the
+/// earlier one.
+pub fn decode(data: &[u8]) -> Result<Decoded<'_>, FramingError> {
+    let packet_end = next_marker(data, 0, PACKET_MARKER).ok_or(FramingError::NoPacket)?;
+    let packet_start = packet_end - PACKET_MARKER;
+    let mut message_ends = vec![];
+    let mut from = packet_end;
+    while let Some(end) = next_marker(data, from, MESSAGE_MARKER) {
+        message_ends.push(end);
+        from = end;
+    }
+    let Some(&first_message_end) = message_ends.first() else {
+        return Err(FramingError::NoMessage {
+            packet: packet_start,
+        });
+    };
+    let messages = message_ends
+        .iter()
+        .enumerate()
+        .map(|(i, &end)| {
+            let offset = end - MESSAGE_MARKER;
+            let payload_end = message_ends
+                .get(i + 1)
+                .map_or(data.len(), |next| next - MESSAGE_MARKER);
+            Message {
+                offset,
+                marker: &data[offset..end],
+                payload: &data[end..payload_end],
+            }
+        })
+        .collect();
+    Ok(Decoded {
+        preamble: &data[..packet_start],
+        packet: Packet {
+            offset: packet_start,
+            marker: &data[packet_start..packet_end],
+            header: &data[packet_end..first_message_end - MESSAGE_MARKER],
+        },
+        messages,
+    })
+}
+
+/// Where the first marker of length `n` starting at or after `from` ends.
+fn next_marker(data: &[u8], from: usize, n: usize) -> Option<usize> {
+    stream::find_marker(&data[from..], n)
+        .expect("reading from memory can't fail")
+        .ok()
+        .map(|end| from + end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let data = b"aaabcdaaaabcdefghijklmnzzopqrstuvwxyzabcc";
+        let decoded = decode(data).unwrap();
+        assert_eq!(decoded.preamble, b"aa");
+        assert_eq!(
+            decoded.packet,
+            Packet {
+                offset: 2,
+                marker: b"abcd",
+                header: b"aaa",
+            }
+        );
+        assert_eq!(
+            decoded.messages,
+            [
+                Message {
+                    offset: 9,
+                    marker: b"abcdefghijklmn",
+                    payload: b"zz",
+                },
+                Message {
+                    offset: 25,
+                    marker: b"opqrstuvwxyzab",
+                    payload: b"cc",
+                },
+            ]
+        );
+        assert_eq!(decoded.messages[1].payload_offset(), 39);
+    }
+
+    #[test]
+    fn test_framing_errors() {
+        assert_eq!(decode(b"abab"), Err(FramingError::NoPacket));
+        assert_eq!(
+            decode(b"aabcdefghijklm"),
+            Err(FramingError::NoMessage { packet: 1 })
+        );
+    }
+
+    #[test]
+    fn test_input() {
+        let input = include_bytes!("../input");
+        let decoded = decode(input).unwrap();
+        assert_eq!(
+            decoded.packet.offset + PACKET_MARKER,
+            crate::solve(input, 4).unwrap()
+        );
+        assert_eq!(
+            decoded.messages[0].payload_offset(),
+            crate::solve(input, 14).unwrap()
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    io::{self, BufReader, Read},
+    ops::Range,
+};
+
+/// Every marker of one length in the datastream.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Markers {
+    pub len: usize,
+    /// Positions where a marker ends, counted like the puzzle does: the number of characters read
+    /// to get to the end of the marker. Consecutive positions are grouped into ranges.
+    pub ends: Vec<Range<usize>>,
+}
+
+impl Markers {
+    /// Where the first marker ends, if there is one.
+    pub fn first(&self) -> Option<usize> {
+        self.ends.first().map(|range| range.start)
+    }
+
+    /// How many markers there are.
+    pub fn count(&self) -> usize {
+        self.ends.iter().map(ExactSizeIterator::len).sum()
+    }
+}
+
+/// Everything found by scanning a datastream.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Scan {
+    /// One for each marker length asked for, in the same order.
+    pub markers: Vec<Markers>,
+    /// The longest stretch of the datastream where every character is different,
+    /// as 0-based byte offsets. If there are several, it's the first.
+    pub longest_distinct: Range<usize>,
+}
+
+/// Read the whole datastream once, finding every marker of each of the given lengths.
+///
+/// Tracks where each byte value was last seen, so at every position it knows how long the
+/// all-different run of bytes ending there is. A marker of length `n` ends wherever that run
+/// is at least `n` long, so any number of lengths can be checked at once.
+pub fn scan(reader: impl Read, lengths: &[usize]) -> io::Result<Scan> {
+    let mut markers: Vec<Markers> = lengths
+        .iter()
+        .map(|&len| Markers {
+            len,
+            ends: Vec::new(),
+        })
+        .collect();
+    let mut last_seen = [None; 256];
+    // The current all-different run starts at this offset.
+    let mut run_start = 0;
+    let mut longest_distinct = 0..0;
+    for (offset, byte) in BufReader::new(reader).bytes().enumerate() {
+        let byte = byte?;
+        if let Some(previous) = last_seen[byte as usize] {
+            run_start = run_start.max(previous + 1);
+        }
+        last_seen[byte as usize] = Some(offset);
+        let run = run_start..offset + 1;
+        if run.len() > longest_distinct.len() {
+            longest_distinct = run.clone();
+        }
+        let position = offset + 1;
+        for m in markers.iter_mut().filter(|m| m.len <= run.len()) {
+            match m.ends.last_mut() {
+                Some(range) if range.end == position => range.end += 1,
+                _ => m.ends.push(position..position + 1),
+            }
+        }
+    }
+    Ok(Scan {
+        markers,
+        longest_distinct,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stream;
+
+    #[test]
+    fn test_every_marker() {
+        let scan = scan(&b"aabcdxxxyz"[..], &[4, 3, 1]).unwrap();
+        assert_eq!(scan.markers[0].ends, vec![5..7]);
+        assert_eq!(scan.markers[1].ends, vec![4..7, 10..11]);
+        assert_eq!(scan.markers[2].count(), 10);
+        assert_eq!(scan.longest_distinct, 1..6);
+    }
+
+    #[test]
+    fn test_first_matches_stream() {
+        let input = include_bytes!("../input");
+        let lengths = [1, 2, 4, 8, 14, 20];
+        let scan = scan(&input[..], &lengths).unwrap();
+        for (n, markers) in lengths.into_iter().zip(&scan.markers) {
+            assert_eq!(
+                markers.first(),
+                stream::find_marker(&input[..], n).unwrap().ok()
+            );
+        }
+        assert!(scan.longest_distinct.len() >= 14);
+    }
+
+    #[test]
+    fn test_empty() {
+        let scan = scan(&b""[..], &[4]).unwrap();
+        assert_eq!(scan.markers[0].first(), None);
+        assert_eq!(scan.longest_distinct, 0..0);
+    }
+}
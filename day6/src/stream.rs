@@ -0,0 +1,398 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    fmt,
+    hash::Hash,
+    io::{self, BufReader, Read},
+    ops::Range,
+    str::FromStr,
+};
+
+/// What the last `n` symbols have to look like to count as a marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Criterion {
+    /// Every symbol is different.
+    AllDistinct,
+    /// At most this many symbols are repeats of an earlier symbol in the window.
+    AtMostRepeats(usize),
+    /// No symbol is the same as the one right before it.
+    NoAdjacentRepeats,
+}
+
+impl FromStr for Criterion {
+    type Err = String;
+
+    /// `distinct`, `repeats=K` or `no-adjacent`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "distinct" => Ok(Criterion::AllDistinct),
+            "no-adjacent" => Ok(Criterion::NoAdjacentRepeats),
+            _ => s
+                .strip_prefix("repeats=")
+                .and_then(|k| k.parse().ok())
+                .map(Criterion::AtMostRepeats)
+                .ok_or_else(|| format!("unknown criterion {s:?}")),
+        }
+    }
+}
+
+/// Counts how many of each symbol are in the window.
+pub trait Tally<T> {
+    /// Count one more `symbol`, returning how many there were before.
+    fn add(&mut self, symbol: &T) -> usize;
+    /// Count one fewer `symbol`, returning how many are left.
+    fn remove(&mut self, symbol: &T) -> usize;
+}
+
+/// Bytes are counted in a fixed table.
+impl Tally<u8> for [usize; 256] {
+    fn add(&mut self, &byte: &u8) -> usize {
+        self[byte as usize] += 1;
+        self[byte as usize] - 1
+    }
+
+    fn remove(&mut self, &byte: &u8) -> usize {
+        self[byte as usize] -= 1;
+        self[byte as usize]
+    }
+}
+
+/// Any other symbols are counted in a map, which only holds the symbols in the window.
+impl<T: Hash + Eq + Clone> Tally<T> for HashMap<T, usize> {
+    fn add(&mut self, symbol: &T) -> usize {
+        let count = self.entry(symbol.clone()).or_default();
+        *count += 1;
+        *count - 1
+    }
+
+    fn remove(&mut self, symbol: &T) -> usize {
+        let count = self.get_mut(symbol).expect("symbol is in the window");
+        *count -= 1;
+        let left = *count;
+        if left == 0 {
+            self.remove(symbol);
+        }
+        left
+    }
+}
+
+/// Watches a datastream one symbol at a time, tracking whether the last `n` symbols make a
+/// marker. Only keeps the last `n` symbols and a count of each one in them,
+/// so each symbol takes the same amount of work no matter how big `n` is.
+pub struct Detector<T = u8, C = [usize; 256]> {
+    n: usize,
+    criterion: Criterion,
+    /// The last `n` symbols.
+    window: VecDeque<T>,
+    /// How many times each symbol appears in the window.
+    counts: C,
+    /// How many symbols in the window are repeats of an earlier symbol in it.
+    repeats: usize,
+    /// How many symbols in the window are the same as the one before.
+    adjacent: usize,
+    /// How many symbols have been pushed so far.
+    position: usize,
+}
+
+impl Detector {
+    /// Look for bytes which are all different.
+    pub fn new(n: usize) -> Self {
+        Detector::with_tally(n, [0; 256])
+    }
+}
+
+impl<T: Hash + Eq + Clone> Detector<T, HashMap<T, usize>> {
+    /// Look for any kind of symbol, such as `u16` tokens or words.
+    pub fn symbols(n: usize) -> Self {
+        Detector::with_tally(n, HashMap::new())
+    }
+}
+
+impl<T: PartialEq, C: Tally<T>> Detector<T, C> {
+    /// Panics if `n` is 0, so the searches below check for that first and return
+    /// [`NoMarker::ZeroLength`] instead.
+    fn with_tally(n: usize, counts: C) -> Self {
+        assert!(n > 0, "markers must be at least one character long");
+        Self {
+            n,
+            criterion: Criterion::AllDistinct,
+            window: VecDeque::with_capacity(n),
+            counts,
+            repeats: 0,
+            adjacent: 0,
+            position: 0,
+        }
+    }
+
+    /// Look for markers meeting `criterion` instead of all different symbols.
+    pub fn criterion(mut self, criterion: Criterion) -> Self {
+        self.criterion = criterion;
+        self
+    }
+
+    /// Receive the next symbol of the datastream. Returns true if it completes a marker.
+    pub fn push(&mut self, symbol: T) -> bool {
+        if self.window.len() == self.n {
+            let leaving = self.window.pop_front().expect("window is full");
+            if self.window.front() == Some(&leaving) {
+                self.adjacent -= 1;
+            }
+            if self.counts.remove(&leaving) > 0 {
+                self.repeats -= 1;
+            }
+        }
+        if self.window.back() == Some(&symbol) {
+            self.adjacent += 1;
+        }
+        if self.counts.add(&symbol) > 0 {
+            self.repeats += 1;
+        }
+        self.window.push_back(symbol);
+        self.position += 1;
+        self.window.len() == self.n
+            && match self.criterion {
+                Criterion::AllDistinct => self.repeats == 0,
+                Criterion::AtMostRepeats(k) => self.repeats <= k,
+                Criterion::NoAdjacentRepeats => self.adjacent == 0,
+            }
+    }
+
+    /// How many symbols have been received so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// How many duplicate symbols in the window count against the criterion: the repeats of
+    /// an earlier symbol, or for [`Criterion::NoAdjacentRepeats`], the repeats of the symbol
+    /// right before. The fewer there are, the closer the window is to being a marker.
+    pub fn duplicates(&self) -> usize {
+        match self.criterion {
+            Criterion::AllDistinct | Criterion::AtMostRepeats(_) => self.repeats,
+            Criterion::NoAdjacentRepeats => self.adjacent,
+        }
+    }
+}
+
+/// Why there's no marker in a datastream.
+#[derive(Debug, PartialEq, Eq)]
+pub enum NoMarker {
+    /// A marker has to be at least one symbol long.
+    ZeroLength,
+    /// The whole datastream is only `len` symbols, not enough to hold a marker of length `n`.
+    TooShort { len: usize, n: usize },
+    /// No window met the criterion. `closest` is the first window with the fewest
+    /// [`Detector::duplicates`], as 0-based offsets, and `duplicates` is how many it had.
+    NotFound {
+        closest: Range<usize>,
+        duplicates: usize,
+    },
+}
+
+impl fmt::Display for NoMarker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoMarker::ZeroLength => write!(f, "markers must be at least one symbol long"),
+            NoMarker::TooShort { len, n } => write!(
+                f,
+                "the datastream is only {len} long, too short for a marker of length {n}"
+            ),
+            NoMarker::NotFound {
+                closest,
+                duplicates,
+            } => write!(
+                f,
+                "no marker found; the closest was from {} to {} with {duplicates} duplicate{}",
+                closest.start,
+                closest.end,
+                if *duplicates == 1 { "" } else { "s" }
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NoMarker {}
+
+/// Read the datastream until the first marker of length `n`, returning how many bytes were
+/// read to get to the end of it, or why there isn't one if the stream ends first.
+/// Stops reading as soon as the marker is found.
+pub fn find_marker(reader: impl Read, n: usize) -> io::Result<Result<usize, NoMarker>> {
+    find_marker_with(reader, n, Criterion::AllDistinct)
+}
+
+/// Like [`find_marker`], but for markers meeting some other criterion.
+pub fn find_marker_with(
+    reader: impl Read,
+    n: usize,
+    criterion: Criterion,
+) -> io::Result<Result<usize, NoMarker>> {
+    if n == 0 {
+        return Ok(Err(NoMarker::ZeroLength));
+    }
+    search(
+        Detector::new(n).criterion(criterion),
+        BufReader::new(reader).bytes(),
+    )
+}
+
+/// Find the first marker of length `n` in a sequence of any kind of symbol, returning how many
+/// symbols were taken to get to the end of it, or why there isn't one.
+pub fn find_symbol_marker<T: Hash + Eq + Clone>(
+    symbols: impl IntoIterator<Item = T>,
+    n: usize,
+    criterion: Criterion,
+) -> Result<usize, NoMarker> {
+    if n == 0 {
+        return Err(NoMarker::ZeroLength);
+    }
+    let detector = Detector::symbols(n).criterion(criterion);
+    let Ok(found) = search(detector, symbols.into_iter().map(Ok::<_, Infallible>));
+    found
+}
+
+/// Feed symbols to the detector until it finds a marker, keeping track of the closest window
+/// in case it doesn't. Stops at the first error reading the symbols.
+fn search<T: PartialEq, C: Tally<T>, E>(
+    mut detector: Detector<T, C>,
+    symbols: impl IntoIterator<Item = Result<T, E>>,
+) -> Result<Result<usize, NoMarker>, E> {
+    let n = detector.n;
+    let mut closest: Option<(usize, usize)> = None;
+    for symbol in symbols {
+        if detector.push(symbol?) {
+            return Ok(Ok(detector.position()));
+        }
+        let position = detector.position();
+        if position >= n && closest.is_none_or(|(_, fewest)| detector.duplicates() < fewest) {
+            closest = Some((position, detector.duplicates()));
+        }
+    }
+    Ok(Err(match closest {
+        Some((end, duplicates)) => NoMarker::NotFound {
+            closest: end - n..end,
+            duplicates,
+        },
+        None => NoMarker::TooShort {
+            len: detector.position(),
+            n,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        let examples = [
+            ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+            ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+        ];
+        for (datastream, packet, message) in examples {
+            assert_eq!(find_marker(datastream.as_bytes(), 4).unwrap(), Ok(packet));
+            assert_eq!(find_marker(datastream.as_bytes(), 14).unwrap(), Ok(message));
+        }
+    }
+
+    #[test]
+    fn test_no_marker() {
+        assert_eq!(
+            find_marker(&b"abcabcabc"[..], 4).unwrap(),
+            Err(NoMarker::NotFound {
+                closest: 0..4,
+                duplicates: 1
+            })
+        );
+        assert_eq!(
+            find_marker(&b"aaaaabcb"[..], 5).unwrap(),
+            Err(NoMarker::NotFound {
+                closest: 2..7,
+                duplicates: 2
+            })
+        );
+        assert_eq!(
+            find_marker(&b"ab"[..], 4).unwrap(),
+            Err(NoMarker::TooShort { len: 2, n: 4 })
+        );
+        assert_eq!(find_marker(&b"a"[..], 1).unwrap(), Ok(1));
+        assert_eq!(
+            find_marker(&b"abc"[..], 0).unwrap(),
+            Err(NoMarker::ZeroLength)
+        );
+        assert_eq!(
+            find_symbol_marker([1u16, 2], 0, Criterion::AllDistinct),
+            Err(NoMarker::ZeroLength)
+        );
+    }
+
+    #[test]
+    fn test_every_position() {
+        let mut detector = Detector::new(4);
+        let found: Vec<_> = b"aabcdxxxx".iter().map(|&b| detector.push(b)).collect();
+        // "abcd" and "bcdx" are both markers.
+        assert_eq!(
+            found,
+            [false, false, false, false, true, true, false, false, false]
+        );
+        assert_eq!(detector.position(), 9);
+    }
+
+    #[test]
+    fn test_criteria() {
+        let data = b"aabbaabbcc";
+        let search = |n, criterion| find_marker_with(&data[..], n, criterion).unwrap().ok();
+        assert_eq!(search(2, Criterion::AllDistinct), Some(3));
+        assert_eq!(search(4, Criterion::AllDistinct), None);
+        assert_eq!(search(4, Criterion::AtMostRepeats(1)), Some(9));
+        assert_eq!(search(4, Criterion::AtMostRepeats(2)), Some(4));
+        assert_eq!(search(3, Criterion::NoAdjacentRepeats), None);
+        assert_eq!(search(1, Criterion::NoAdjacentRepeats), Some(1));
+        // "aaab" and "aaba" both have two repeats, but "aaba" has fewer adjacent ones.
+        assert_eq!(
+            find_marker_with(&b"aaaba"[..], 4, Criterion::NoAdjacentRepeats).unwrap(),
+            Err(NoMarker::NotFound {
+                closest: 1..5,
+                duplicates: 1
+            })
+        );
+        assert_eq!("repeats=3".parse(), Ok(Criterion::AtMostRepeats(3)));
+        assert!("repeats".parse::<Criterion>().is_err());
+    }
+
+    #[test]
+    fn test_symbols() {
+        let tokens: [u16; 6] = [1000, 1000, 2000, 1000, 3000, 4000];
+        assert_eq!(find_symbol_marker(tokens, 3, Criterion::AllDistinct), Ok(5));
+        assert_eq!(
+            find_symbol_marker(tokens, 4, Criterion::NoAdjacentRepeats),
+            Ok(5)
+        );
+        assert_eq!(
+            find_symbol_marker(tokens, 7, Criterion::AllDistinct),
+            Err(NoMarker::TooShort { len: 6, n: 7 })
+        );
+        let words = "the cat sat on the mat on the hat".split_whitespace();
+        assert_eq!(
+            find_symbol_marker(words.clone(), 5, Criterion::AllDistinct),
+            Ok(6)
+        );
+        assert_eq!(
+            find_symbol_marker(words, 6, Criterion::AllDistinct),
+            Err(NoMarker::NotFound {
+                closest: 0..6,
+                duplicates: 1
+            })
+        );
+        // The byte and generic detectors agree.
+        let input = include_bytes!("../input");
+        for n in [4, 14] {
+            assert_eq!(
+                find_symbol_marker(input.iter(), n, Criterion::AllDistinct),
+                find_marker(&input[..], n).unwrap()
+            );
+        }
+    }
+}
@@ -1,78 +1,67 @@
-use std::collections::HashMap;
-
-use parse::Line;
+use tree::{NodeId, Tree};
 
 mod parse;
+mod tree;
 
+/// Usage:
+///   day7
+///     Solve q1 and q2, listing the directories chosen.
+///   day7 <path>
+///     Show the total size of a file or directory, e.g. `/a/e`.
 fn main() {
     let input = include_bytes!("../input");
-    let (_, lines) = parse::parse_lines(input).expect("could not parse input file");
-    let dir_sizes = size_of_dirs(lines);
-    println!("q1: {}", q1(&dir_sizes));
-    println!("q2: {}", q2(&dir_sizes));
-}
-
-/// Given the parsed input, find the size of every directory.
-/// Order of the returned vec is arbitrary.
-fn size_of_dirs(lines: Vec<Line>) -> Vec<usize> {
-    let mut filesizes: HashMap<Vec<String>, usize> = Default::default();
-    let mut cwd = Vec::new(); // cwd = current working directory
-    for line in lines {
-        match line {
-            Line::Cd(dir) => match dir.as_ref() {
-                ".." => {
-                    cwd.pop();
-                }
-                "/" => {
-                    cwd = Vec::new();
-                }
-                normal_dir => cwd.push(normal_dir.to_owned()),
-            },
-            Line::LsFile(size, name) => {
-                let mut absolute_path = cwd.clone();
-                absolute_path.push(name);
-                filesizes.insert(absolute_path, size as _);
+    let lines = match parse::parse_lines(input) {
+        Ok(lines) => lines,
+        Err(errors) => {
+            for error in errors {
+                eprintln!("{error}");
             }
-            Line::Other => {}
+            std::process::exit(1);
         }
-    }
-
-    // Track the size of each directory.
-    let mut dir_sizes: HashMap<Vec<String>, usize> = Default::default();
-
-    // Loop over every file. Add its size to the size of each parent directory.
-    for (absolute_filepath, size) in filesizes {
-        for i in 0..absolute_filepath.len() {
-            let dir = absolute_filepath[0..i].to_vec();
-            *dir_sizes.entry(dir).or_insert(0) += size;
+    };
+    let tree = Tree::from_transcript(lines).unwrap_or_else(|conflict| {
+        eprintln!("{conflict}");
+        std::process::exit(1);
+    });
+    if let Some(path) = std::env::args().nth(1) {
+        match tree.lookup(&path) {
+            Some(node) => println!("{}: {}", tree.path(node), tree.size(node)),
+            None => println!("{path}: not found"),
         }
+        return;
     }
-
-    dir_sizes.values().copied().collect()
+    let (total, chosen) = q1(&tree);
+    println!("q1: {total}");
+    for dir in chosen {
+        println!("  {} ({})", tree.path(dir), tree.size(dir));
+    }
+    let chosen = q2(&tree);
+    println!("q2: {}", tree.size(chosen));
+    println!("  {}", tree.path(chosen));
 }
 
 /// Find all of the directories with a total size of at most 100000. What is the sum of the total
-/// sizes of those directories?
-fn q1(dir_sizes: &[usize]) -> usize {
+/// sizes of those directories? Also returns which directories they were.
+fn q1(tree: &Tree) -> (usize, Vec<NodeId>) {
     const MAX_TOTAL_SIZE: usize = 100_000;
-    dir_sizes
-        .iter()
-        .filter(|size| size <= &&MAX_TOTAL_SIZE)
-        .sum()
+    let chosen: Vec<_> = tree
+        .dirs()
+        .filter(|&dir| tree.size(dir) <= MAX_TOTAL_SIZE)
+        .collect();
+    (chosen.iter().map(|&dir| tree.size(dir)).sum(), chosen)
 }
 
 /// Find the smallest directory that, if deleted, would free up enough space on the filesystem to
-/// run the update. What is the total size of that directory?
-fn q2(dir_sizes: &[usize]) -> usize {
-    let bytes_in_use = *dir_sizes.iter().max().unwrap();
+/// run the update. Returns that directory.
+fn q2(tree: &Tree) -> NodeId {
+    let bytes_in_use = tree.size(tree.root());
     const DISK_SIZE: usize = 70000000;
     let bytes_unused = DISK_SIZE - bytes_in_use;
     const BYTES_REQUIRED: usize = 30000000;
     let bytes_to_delete = BYTES_REQUIRED - bytes_unused;
-    *dir_sizes
-        .iter()
-        .filter(|dir_size| dir_size >= &&bytes_to_delete)
-        .min()
+    tree.dirs()
+        .filter(|&dir| tree.size(dir) >= bytes_to_delete)
+        .min_by_key(|&dir| tree.size(dir))
         .unwrap()
 }
 
@@ -83,9 +72,21 @@ mod tests {
     #[test]
     fn test_real_answer() {
         let input = include_bytes!("../input");
-        let (_, lines) = parse::parse_lines(input).expect("could not parse input file");
-        let dir_sizes = size_of_dirs(lines);
-        assert_eq!(1390824, q1(&dir_sizes));
-        assert_eq!(7490863, q2(&dir_sizes));
+        let lines = parse::parse_lines(input).expect("could not parse input file");
+        let tree = Tree::from_transcript(lines).unwrap();
+        assert_eq!(1390824, q1(&tree).0);
+        assert_eq!(7490863, tree.size(q2(&tree)));
+    }
+
+    #[test]
+    fn test_example_choices() {
+        let input = include_bytes!("../example");
+        let lines = parse::parse_lines(input).unwrap();
+        let tree = Tree::from_transcript(lines).unwrap();
+        let (total, chosen) = q1(&tree);
+        assert_eq!(total, 95437);
+        let paths: Vec<_> = chosen.into_iter().map(|dir| tree.path(dir)).collect();
+        assert_eq!(paths, ["/a", "/a/e"]);
+        assert_eq!(tree.path(q2(&tree)), "/d");
     }
 }
@@ -1,54 +1,207 @@
+use std::fmt;
+
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_until1, take_while},
-    character::{
-        complete::{alpha1, char as one_char, line_ending, u32 as parse_num},
-        is_alphabetic,
-    },
-    combinator::{map, map_res, value},
-    multi::separated_list1,
-    sequence::{preceded, tuple},
+    bytes::complete::{escaped_transform, is_not, tag, take, take_while, take_while1},
+    character::complete::{char as one_char, u32 as parse_num},
+    combinator::{all_consuming, map, map_res, rest, value, verify},
+    sequence::{delimited, preceded, separated_pair},
 };
 
 type Input<'a> = &'a [u8];
 type Result<'a, T> = nom::IResult<Input<'a>, T>;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Line {
+    /// `$ cd <dir>`
     Cd(String),
+    /// `$ ls`
+    Ls,
+    /// `dir <name>`, listed by ls.
+    LsDir(String),
+    /// `<size> <name>`, listed by ls.
     LsFile(u32, String),
-    Other,
 }
 
-/// Parse the entire input file.
-pub fn parse_lines(i: Input) -> Result<Vec<Line>> {
-    separated_list1(line_ending, alt((parse_cd, parse_ls_file, parse_other)))(i)
+/// A transcript line that isn't a cd or ls, or what ls printed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    /// Which line of the transcript, counting from 1.
+    pub line: usize,
+    pub kind: ErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A command other than cd or ls, e.g. `$ rm -rf /`.
+    UnknownCommand(String),
+    /// A directory or file listed when the last command wasn't ls.
+    OutputWithoutLs,
+    /// The line doesn't look like the thing it should be.
+    Syntax(Expected),
+}
+
+/// What a line that couldn't be parsed should have looked like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expected {
+    /// A `$` line which isn't `$ <command>`.
+    Command,
+    /// `$ cd` needs exactly one directory, quoted if it has spaces.
+    CdTarget,
+    /// `$ ls` doesn't take any options or arguments.
+    LsWithoutArguments,
+    /// Anything else should be `dir <name>` or `<size> <name>`.
+    Listing,
 }
 
-fn parse_cd(i: Input) -> Result<Line> {
-    fn parse_dir_name(i: Input) -> Result<String> {
-        map_res(alt((tag("/"), tag(".."), alpha1)), |bytes: &[u8]| {
-            String::from_utf8(bytes.to_vec())
-        })(i)
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Expected::Command => "a command after `$ `",
+            Expected::CdTarget => "`$ cd` followed by one directory",
+            Expected::LsWithoutArguments => "`$ ls` on its own",
+            Expected::Listing => "`dir <name>` or `<size> <name>`",
+        })
     }
-    map(preceded(tag("$ cd "), parse_dir_name), Line::Cd)(i)
 }
 
-fn parse_other(i: Input) -> Result<Line> {
-    value(Line::Other, take_until1("\n"))(i)
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "transcript line {}: ", self.line)?;
+        match &self.kind {
+            ErrorKind::UnknownCommand(command) => write!(f, "unknown command {command:?}"),
+            ErrorKind::OutputWithoutLs => write!(f, "listing without an ls before it"),
+            ErrorKind::Syntax(expected) => write!(f, "expected {expected}"),
+        }
+    }
 }
 
-fn parse_ls_file(i: Input) -> Result<Line> {
-    fn parse_file_name(i: Input) -> Result<String> {
-        const PERIOD: u8 = 46;
-        map_res(
-            take_while(|c| is_alphabetic(c) || c == PERIOD),
-            |bytes: &[u8]| String::from_utf8(bytes.to_vec()),
-        )(i)
+impl std::error::Error for Error {}
+
+/// Parse a whole transcript, one [`Line`] for each line of it, or every line that's wrong.
+/// Trailing whitespace on a line is ignored, so CRLF transcripts work too.
+pub fn parse_lines(transcript: &[u8]) -> std::result::Result<Vec<Line>, Vec<Error>> {
+    let transcript = transcript.strip_suffix(b"\n").unwrap_or(transcript);
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+    // Whether the lines so far are the output of an ls.
+    let mut listing = false;
+    // Whether the lines so far are the output of a command that was rejected. They've got
+    // nothing to do with the filesystem, so they're skipped until the next command.
+    let mut skipping = false;
+    for (line, text) in (1..).zip(transcript.split(|&byte| byte == b'\n')) {
+        let text = text.trim_ascii_end();
+        let is_command = text.starts_with(b"$");
+        if skipping && !is_command {
+            continue;
+        }
+        skipping = false;
+        match all_consuming(parse_line)(text) {
+            Ok((_, Ok(parsed))) => {
+                let is_output = matches!(parsed, Line::LsDir(_) | Line::LsFile(..));
+                if is_output && !listing {
+                    errors.push(Error {
+                        line,
+                        kind: ErrorKind::OutputWithoutLs,
+                    });
+                }
+                listing = is_output || parsed == Line::Ls;
+                lines.push(parsed);
+            }
+            Ok((_, Err(kind))) => {
+                skipping = true;
+                errors.push(Error { line, kind });
+            }
+            Err(_) => {
+                // A bad command's output is skipped, but one bad line of ls output doesn't
+                // stop the rest of the listing from counting.
+                skipping = is_command;
+                let expected = if is_command {
+                    Expected::Command
+                } else {
+                    Expected::Listing
+                };
+                errors.push(Error {
+                    line,
+                    kind: ErrorKind::Syntax(expected),
+                });
+            }
+        }
     }
+    if errors.is_empty() {
+        Ok(lines)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parse one line, which may turn out to be a command that doesn't exist.
+fn parse_line(i: Input) -> Result<std::result::Result<Line, ErrorKind>> {
+    alt((
+        map(all_consuming(parse_command), Ok),
+        map(preceded(tag("$ "), rest), |command: &[u8]| {
+            match command.split(|&c| c == b' ').next().unwrap_or_default() {
+                // A command that exists, but with the wrong arguments.
+                b"cd" => Err(ErrorKind::Syntax(Expected::CdTarget)),
+                b"ls" => Err(ErrorKind::Syntax(Expected::LsWithoutArguments)),
+                name => Err(ErrorKind::UnknownCommand(
+                    String::from_utf8_lossy(name).into_owned(),
+                )),
+            }
+        }),
+        map(alt((parse_ls_dir, parse_ls_file)), Ok),
+    ))(i)
+}
+
+fn parse_command(i: Input) -> Result<Line> {
+    alt((
+        map(preceded(tag("$ cd "), parse_name), Line::Cd),
+        value(Line::Ls, tag("$ ls")),
+    ))(i)
+}
+
+/// A file or directory name, written the way a shell would: either anything without whitespace,
+/// or in quotes if it has spaces in it, e.g. `'my documents'` or `"it's.txt"`.
+/// Inside double quotes, a backslash escapes the next character. Empty names like `''` aren't
+/// allowed, since no real file could have one.
+fn parse_name(i: Input) -> Result<String> {
+    let single_quoted = delimited(one_char('\''), take_while(|c| c != b'\''), one_char('\''));
+    let double_quoted = delimited(
+        one_char('"'),
+        escaped_transform(is_not("\\\""), '\\', take(1usize)),
+        one_char('"'),
+    );
+    let unquoted = verify(
+        take_while1(|c: u8| !c.is_ascii_whitespace()),
+        |name: &[u8]| !name.starts_with(b"'") && !name.starts_with(b"\""),
+    );
+    let name = alt((
+        map(single_quoted, <[u8]>::to_vec),
+        double_quoted,
+        map(unquoted, <[u8]>::to_vec),
+    ));
+    map_res(
+        verify(name, |name: &Vec<u8>| !name.is_empty()),
+        String::from_utf8,
+    )(i)
+}
+
+/// The name of something ls listed. Unlike a cd target, it's a single name rather than a path,
+/// so it can't contain `/` or be `.` or `..`.
+fn parse_entry_name(i: Input) -> Result<String> {
+    verify(parse_name, |name: &String| {
+        !name.contains('/') && name != "." && name != ".."
+    })(i)
+}
+
+fn parse_ls_dir(i: Input) -> Result<Line> {
+    map(preceded(tag("dir "), parse_entry_name), Line::LsDir)(i)
+}
+
+fn parse_ls_file(i: Input) -> Result<Line> {
     map(
-        tuple((parse_num, one_char(' '), parse_file_name)),
-        |(size, _, name)| Line::LsFile(size, name),
+        separated_pair(parse_num, one_char(' '), parse_entry_name),
+        |(size, name)| Line::LsFile(size, name),
     )(i)
 }
 
@@ -59,14 +212,127 @@ mod tests {
     #[test]
     fn test_parse_example() {
         let input = include_bytes!("../example");
-        let (_, lines) = parse_lines(input).unwrap();
+        let lines = parse_lines(input).unwrap();
         assert_eq!(lines.len(), 23);
+        assert_eq!(
+            lines[..4],
+            [
+                Line::Cd("/".to_owned()),
+                Line::Ls,
+                Line::LsDir("a".to_owned()),
+                Line::LsFile(14848514, "b.txt".to_owned()),
+            ]
+        );
     }
 
     #[test]
     fn test_parse_input() {
         let input = include_bytes!("../input");
-        let (_, lines) = parse_lines(input).unwrap();
+        let lines = parse_lines(input).unwrap();
         assert_eq!(lines.len(), 950);
     }
+
+    #[test]
+    fn test_errors() {
+        let input = b"$ cd /\n12 a\n$ ls\ndir b\n$ rm -rf b\n$ cd b\n???\n$ ls -l\n$ cd a b\n$\n";
+        let errors = parse_lines(input).unwrap_err();
+        assert_eq!(
+            errors,
+            [
+                Error {
+                    line: 2,
+                    kind: ErrorKind::OutputWithoutLs,
+                },
+                Error {
+                    line: 5,
+                    kind: ErrorKind::UnknownCommand("rm".to_owned()),
+                },
+                Error {
+                    line: 7,
+                    kind: ErrorKind::Syntax(Expected::Listing),
+                },
+                Error {
+                    line: 8,
+                    kind: ErrorKind::Syntax(Expected::LsWithoutArguments),
+                },
+                Error {
+                    line: 9,
+                    kind: ErrorKind::Syntax(Expected::CdTarget),
+                },
+                Error {
+                    line: 10,
+                    kind: ErrorKind::Syntax(Expected::Command),
+                },
+            ]
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "transcript line 5: unknown command \"rm\""
+        );
+        assert_eq!(
+            errors[2].to_string(),
+            "transcript line 7: expected `dir <name>` or `<size> <name>`"
+        );
+    }
+
+    #[test]
+    fn test_rejected_command_output() {
+        let input = b"$ cd /\n$ ls -la\ndir a\n1 b\n$ find .\n./a\n$ ls\n2 c\n??\n3 d\n$ cd a";
+        let errors = parse_lines(input).unwrap_err();
+        let lines: Vec<_> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, [2, 5, 9]);
+    }
+
+    #[test]
+    fn test_names() {
+        let input = "$ cd build-2\n$ ls\n100 my_file.tar.gz\ndir ünïcødé\n\
+            30 'read me.txt'\n5 \"it's \\\"quoted\\\".txt\"\n$ cd 'my documents'";
+        assert_eq!(
+            parse_lines(input.as_bytes()).unwrap(),
+            [
+                Line::Cd("build-2".to_owned()),
+                Line::Ls,
+                Line::LsFile(100, "my_file.tar.gz".to_owned()),
+                Line::LsDir("ünïcødé".to_owned()),
+                Line::LsFile(30, "read me.txt".to_owned()),
+                Line::LsFile(5, "it's \"quoted\".txt".to_owned()),
+                Line::Cd("my documents".to_owned()),
+            ]
+        );
+        // Spaces need quotes, quotes need closing, and names can't be empty.
+        let input = b"$ ls\n30 read me.txt\n$ ls\ndir 'oops\n$ ls\ndir ''\n5 \"\"\n$ cd ''";
+        let errors = parse_lines(input).unwrap_err();
+        let expected = |line| Error {
+            line,
+            kind: ErrorKind::Syntax(Expected::Listing),
+        };
+        assert_eq!(
+            errors[..4],
+            [expected(2), expected(4), expected(6), expected(7)]
+        );
+        assert_eq!(errors[4].kind, ErrorKind::Syntax(Expected::CdTarget));
+    }
+
+    #[test]
+    fn test_entry_names_are_not_paths() {
+        let input = b"$ cd /\n$ ls\ndir a/b\n1 ..\ndir .\n2 '/x'\n3 ok\n$ cd a/b";
+        let errors = parse_lines(input).unwrap_err();
+        let expected = |line| Error {
+            line,
+            kind: ErrorKind::Syntax(Expected::Listing),
+        };
+        assert_eq!(errors, [expected(3), expected(4), expected(5), expected(6)]);
+        // A cd target is a path, so it can still have slashes and dots in it.
+        let lines = parse_lines(b"$ cd a/b\n$ cd ../..\n$ cd .").unwrap();
+        assert_eq!(lines[0], Line::Cd("a/b".to_owned()));
+    }
+
+    #[test]
+    fn test_parse_shell_transcript() {
+        let input = include_bytes!("../shell-transcript");
+        let lines = parse_lines(input).unwrap();
+        assert_eq!(lines.len(), 24);
+        assert!(lines.contains(&Line::LsFile(5, "it's.txt".to_owned())));
+        assert!(lines.contains(&Line::Cd("../..".to_owned())));
+    }
 }
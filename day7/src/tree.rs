@@ -0,0 +1,353 @@
+use std::fmt;
+
+use crate::parse::Line;
+
+/// Which node in a [`Tree`] something is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+#[derive(Debug)]
+pub enum Node {
+    Dir(Dir),
+    File(File),
+}
+
+#[derive(Debug)]
+pub struct Dir {
+    pub name: String,
+    /// None for the root directory.
+    pub parent: Option<NodeId>,
+    pub children: Vec<NodeId>,
+}
+
+#[derive(Debug)]
+pub struct File {
+    pub name: String,
+    pub parent: NodeId,
+    pub size: usize,
+}
+
+impl Node {
+    pub fn name(&self) -> &str {
+        match self {
+            Node::Dir(dir) => &dir.name,
+            Node::File(file) => &file.name,
+        }
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        match self {
+            Node::Dir(dir) => dir.parent,
+            Node::File(file) => Some(file.parent),
+        }
+    }
+}
+
+/// The filesystem seen in a terminal transcript.
+/// All the nodes live in one vec, and refer to each other by their index in it.
+#[derive(Debug)]
+pub struct Tree {
+    nodes: Vec<Node>,
+    /// The total size of each node, including everything inside it if it's a directory.
+    sizes: Vec<usize>,
+}
+
+/// A transcript line that contradicts what the transcript showed earlier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// 1-based line of the transcript.
+    pub line: usize,
+    /// The path of the file or directory in question.
+    pub path: String,
+    pub kind: ConflictKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// `cd` into something that was listed as a file.
+    CdIntoFile,
+    /// Listed as a directory, but it was a file before.
+    DirWasFile,
+    /// Listed as a file, but it was a directory before.
+    FileWasDir,
+    /// Listed as a file again, with a different size.
+    SizeChanged { before: usize, after: usize },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Conflict { line, path, kind } = self;
+        match kind {
+            ConflictKind::CdIntoFile => write!(f, "line {line}: can't cd into the file {path}"),
+            ConflictKind::DirWasFile => {
+                write!(f, "line {line}: {path} was a file, now a directory")
+            }
+            ConflictKind::FileWasDir => {
+                write!(f, "line {line}: {path} was a directory, now a file")
+            }
+            ConflictKind::SizeChanged { before, after } => write!(
+                f,
+                "line {line}: {path} was {before} bytes, now {after} bytes"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Conflict {}
+
+impl Tree {
+    /// Replay the transcript, building up every directory and file it shows.
+    /// Each element of `lines` is taken to be one line of the transcript, so the first
+    /// conflict can be reported with its line number.
+    pub fn from_transcript(lines: Vec<Line>) -> Result<Self, Conflict> {
+        let mut tree = Self {
+            nodes: vec![Node::Dir(Dir {
+                name: "/".to_owned(),
+                parent: None,
+                children: Vec::new(),
+            })],
+            sizes: Vec::new(),
+        };
+        let mut cwd = tree.root(); // cwd = current working directory
+        for (i, line) in lines.into_iter().enumerate() {
+            let line_number = i + 1;
+            let conflict = |tree: &Tree, node: NodeId, kind| Conflict {
+                line: line_number,
+                path: tree.path(node),
+                kind,
+            };
+            match line {
+                Line::Cd(path) => {
+                    // Real shells can change to a whole path at once, e.g. `cd ../..`.
+                    if path.starts_with('/') {
+                        cwd = tree.root();
+                    }
+                    for name in path.split('/').filter(|name| !name.is_empty()) {
+                        cwd = match name {
+                            ".." => tree.get(cwd).parent().unwrap_or(cwd),
+                            "." => cwd,
+                            name => tree
+                                .dir(cwd, name)
+                                .map_err(|file| conflict(&tree, file, ConflictKind::CdIntoFile))?,
+                        };
+                    }
+                }
+                Line::Ls => {}
+                Line::LsDir(name) => {
+                    // Recorded even if it's never entered, so empty directories show up.
+                    tree.dir(cwd, &name)
+                        .map_err(|file| conflict(&tree, file, ConflictKind::DirWasFile))?;
+                }
+                Line::LsFile(size, name) => {
+                    let size = size as usize;
+                    match tree.child(cwd, &name).map(|id| (id, tree.get(id))) {
+                        None => {
+                            tree.add(
+                                cwd,
+                                Node::File(File {
+                                    name,
+                                    parent: cwd,
+                                    size,
+                                }),
+                            );
+                        }
+                        // Listing the same directory twice shouldn't count its files twice.
+                        Some((_, Node::File(file))) if file.size == size => {}
+                        Some((id, Node::File(file))) => {
+                            let kind = ConflictKind::SizeChanged {
+                                before: file.size,
+                                after: size,
+                            };
+                            return Err(conflict(&tree, id, kind));
+                        }
+                        Some((id, Node::Dir(_))) => {
+                            return Err(conflict(&tree, id, ConflictKind::FileWasDir))
+                        }
+                    }
+                }
+            }
+        }
+
+        // Children always come after their parent, so going backwards means every node's
+        // size is finished before it's added to its parent.
+        tree.sizes = tree
+            .nodes
+            .iter()
+            .map(|node| match node {
+                Node::Dir(_) => 0,
+                Node::File(file) => file.size,
+            })
+            .collect();
+        for i in (1..tree.nodes.len()).rev() {
+            let NodeId(parent) = tree.nodes[i].parent().expect("only the root has no parent");
+            tree.sizes[parent] += tree.sizes[i];
+        }
+        Ok(tree)
+    }
+
+    /// The directory called `name` inside `parent`, creating it if it hasn't been seen yet.
+    /// Fails with the file's id if there's a file with that name instead.
+    fn dir(&mut self, parent: NodeId, name: &str) -> Result<NodeId, NodeId> {
+        match self.child(parent, name) {
+            Some(id) => match self.get(id) {
+                Node::Dir(_) => Ok(id),
+                Node::File(_) => Err(id),
+            },
+            None => Ok(self.add(
+                parent,
+                Node::Dir(Dir {
+                    name: name.to_owned(),
+                    parent: Some(parent),
+                    children: Vec::new(),
+                }),
+            )),
+        }
+    }
+
+    fn add(&mut self, parent: NodeId, node: Node) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(node);
+        match &mut self.nodes[parent.0] {
+            Node::Dir(dir) => dir.children.push(id),
+            Node::File(_) => unreachable!("only directories are ever a parent"),
+        }
+        id
+    }
+
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    pub fn get(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// The total size of a file or directory.
+    pub fn size(&self, id: NodeId) -> usize {
+        self.sizes[id.0]
+    }
+
+    /// Every directory, in the order they were first seen.
+    pub fn dirs(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len())
+            .map(NodeId)
+            .filter(|&id| matches!(self.get(id), Node::Dir(_)))
+    }
+
+    /// The file or directory called `name` directly inside `dir`.
+    pub fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        match self.get(dir) {
+            Node::Dir(dir) => dir
+                .children
+                .iter()
+                .copied()
+                .find(|&child| self.get(child).name() == name),
+            Node::File(_) => None,
+        }
+    }
+
+    /// Find a node by its absolute path, e.g. `/a/e`.
+    pub fn lookup(&self, path: &str) -> Option<NodeId> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self.root(), |dir, name| self.child(dir, name))
+    }
+
+    /// The absolute path of a node, e.g. `/a/e`.
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names = Vec::new();
+        let mut node = Some(id);
+        while let Some(id) = node {
+            if id != self.root() {
+                names.push(self.get(id).name());
+            }
+            node = self.get(id).parent();
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn example() -> Tree {
+        let input = include_bytes!("../example");
+        let lines = parse::parse_lines(input).unwrap();
+        Tree::from_transcript(lines).unwrap()
+    }
+
+    #[test]
+    fn test_sizes() {
+        let tree = example();
+        let size_of = |path| tree.size(tree.lookup(path).unwrap());
+        assert_eq!(size_of("/a/e"), 584);
+        assert_eq!(size_of("/a"), 94853);
+        assert_eq!(size_of("/d"), 24933642);
+        assert_eq!(size_of("/"), 48381165);
+        assert_eq!(size_of("/d/k"), 7214296);
+        assert_eq!(tree.dirs().count(), 4);
+    }
+
+    #[test]
+    fn test_paths() {
+        let tree = example();
+        for path in ["/", "/a", "/a/e", "/a/e/i", "/d/d.log"] {
+            assert_eq!(tree.path(tree.lookup(path).unwrap()), path);
+        }
+        assert_eq!(tree.lookup("/a/x"), None);
+        assert_eq!(tree.lookup("/b.txt/x"), None);
+        let e = tree.lookup("/a/e").unwrap();
+        assert_eq!(tree.get(e).parent(), tree.lookup("/a"));
+    }
+
+    #[test]
+    fn test_empty_dir() {
+        let input = b"$ cd /\n$ ls\ndir a\n1 b\ndir c\n$ cd a\n$ ls\n5 x\n";
+        let tree = Tree::from_transcript(parse::parse_lines(input).unwrap()).unwrap();
+        let c = tree.lookup("/c").unwrap();
+        assert_eq!(tree.size(c), 0);
+        assert!(matches!(tree.get(c), Node::Dir(Dir { children, .. }) if children.is_empty()));
+        assert_eq!(tree.size(tree.root()), 6);
+        assert_eq!(tree.dirs().count(), 3);
+    }
+
+    #[test]
+    fn test_shell_transcript() {
+        let input = include_bytes!("../shell-transcript");
+        let tree = Tree::from_transcript(parse::parse_lines(input).unwrap()).unwrap();
+        let size_of = |path| tree.size(tree.lookup(path).unwrap());
+        assert_eq!(size_of("/build-2/obj/main.o"), 2000);
+        assert_eq!(size_of("/my documents"), 30);
+        assert_eq!(size_of("/my documents/read me.txt"), 30);
+        assert_eq!(size_of("/ünïcødé/naïve.txt"), 7);
+        assert_eq!(size_of("/it's.txt"), 5);
+        assert_eq!(size_of("/empty.d"), 0);
+        assert_eq!(size_of("/"), 2142);
+        assert_eq!(tree.dirs().count(), 6);
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let conflict = |input: &[u8]| Tree::from_transcript(parse::parse_lines(input).unwrap());
+        assert_eq!(
+            conflict(b"$ ls\n1 a\n$ cd a\n$ ls\n2 b").unwrap_err(),
+            Conflict {
+                line: 3,
+                path: "/a".to_owned(),
+                kind: ConflictKind::CdIntoFile,
+            }
+        );
+        let err = conflict(b"$ ls\ndir a\n5 a").unwrap_err();
+        assert_eq!((err.line, err.kind), (3, ConflictKind::FileWasDir));
+        let err = conflict(b"$ ls\n5 a\n$ ls\ndir a").unwrap_err();
+        assert_eq!((err.line, err.kind), (4, ConflictKind::DirWasFile));
+        let err = conflict(b"$ ls\n5 a\n$ ls\n6 a").unwrap_err();
+        assert_eq!(err.to_string(), "line 4: /a was 5 bytes, now 6 bytes");
+        // Seeing the same file again is fine.
+        let tree = conflict(b"$ ls\n5 a\n$ ls\n5 a").unwrap();
+        assert_eq!(tree.size(tree.root()), 5);
+    }
+}